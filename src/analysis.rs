@@ -0,0 +1,132 @@
+use anyhow::Result;
+use realfft::num_complex::Complex;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::Arc;
+
+pub mod beats;
+pub mod biquad;
+pub mod chroma;
+pub mod crossover;
+pub mod features;
+pub mod gate;
+pub mod levels;
+pub mod loudness;
+pub mod notes;
+pub mod onset;
+pub mod pitch;
+pub mod signal;
+pub mod spectrum;
+pub mod stereo;
+pub mod tempo;
+pub mod waveform;
+
+/// Analysis is never reported faster than this, even at higher OSC sample rates
+const MAX_REPORT_RATE: f32 = 1000.0;
+
+/// Splits host blocks at the OSC sample rate so analysis results are sent at a steady rate
+/// regardless of the host buffer size
+pub struct ReportClock {
+    interval: f64,
+    remaining: f64,
+}
+
+impl Default for ReportClock {
+    fn default() -> Self {
+        Self {
+            interval: 1.0,
+            remaining: 1.0,
+        }
+    }
+}
+
+impl ReportClock {
+    pub fn set_rate(&mut self, sample_rate: f32, report_rate: f32) {
+        let interval = (sample_rate / report_rate.clamp(1.0, MAX_REPORT_RATE)).max(1.0) as f64;
+        if interval != self.interval {
+            self.interval = interval;
+            self.remaining = self.remaining.min(interval);
+        }
+    }
+
+    /// Samples between report points
+    pub fn interval(&self) -> f64 {
+        self.interval
+    }
+
+    /// Returns the next run of samples starting at `start`, the flag is set when the run ends
+    /// on a report point
+    pub fn next_segment(&mut self, start: usize, num_samples: usize) -> (Range<usize>, bool) {
+        let len = (self.remaining.ceil() as usize).clamp(1, num_samples - start);
+        self.remaining -= len as f64;
+        let report = self.remaining <= 0.0;
+        if report {
+            self.remaining += self.interval;
+        }
+        (start..start + len, report)
+    }
+}
+
+/// Average of every channel at sample `index`, `channels` can't be empty
+pub fn mono_sample<C: AsRef<[f32]>>(channels: &[C], index: usize) -> f32 {
+    let sum: f32 = channels.iter().map(|channel| channel.as_ref()[index]).sum();
+    sum / channels.len() as f32
+}
+
+/// The most recent samples with a Hann windowed FFT over them. The window is normalized so a
+/// full scale sine reads as a magnitude of 1.0.
+pub struct WindowedFft {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    history: Vec<f32>,
+    history_pos: usize,
+    input: Vec<f32>,
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+impl WindowedFft {
+    pub fn new(size: usize) -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(size);
+        let window: Vec<f32> = (0..size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / size as f32).cos())
+            .collect();
+        let window_sum: f32 = window.iter().sum();
+        Self {
+            input: fft.make_input_vec(),
+            output: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+            window: window.iter().map(|w| w * 2.0 / window_sum).collect(),
+            history: vec![0.0; size],
+            history_pos: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.history.fill(0.0);
+        self.history_pos = 0;
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        self.history[self.history_pos] = sample;
+        self.history_pos = (self.history_pos + 1) % self.history.len();
+    }
+
+    /// Runs the FFT over the window and returns its bins
+    pub fn process(&mut self) -> Result<&[Complex<f32>]> {
+        let (newest, oldest) = self.history.split_at(self.history_pos);
+        for ((input, sample), window) in self
+            .input
+            .iter_mut()
+            .zip(oldest.iter().chain(newest))
+            .zip(&self.window)
+        {
+            *input = sample * window;
+        }
+        self.fft
+            .process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch)?;
+        Ok(&self.output)
+    }
+}
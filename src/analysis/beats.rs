@@ -0,0 +1,94 @@
+use super::crossover::MAX_CROSSOVER_BANDS;
+
+/// RMS, every crossover band and the centroid
+pub const MAX_BEAT_FEATURES: usize = MAX_CROSSOVER_BANDS + 2;
+
+/// Tolerance for rounding in the host position between segments
+const POSITION_EPSILON: f64 = 1e-6;
+
+/// Bar and beat inside the bar, both counted from 1
+#[derive(Clone, Copy, PartialEq)]
+pub struct BeatPosition {
+    pub bar: i32,
+    pub beat: i32,
+}
+
+impl BeatPosition {
+    /// Finds the beat containing `pos_beats`, which is counted in quarter notes like the host
+    /// transport
+    pub fn from_quarter_notes(pos_beats: f64, numerator: i32, denominator: i32) -> Self {
+        let numerator = numerator.max(1) as i64;
+        let beat_length = 4.0 / denominator.max(1) as f64;
+        let beat = (pos_beats / beat_length + 1e-6).floor() as i64;
+        Self {
+            bar: beat.div_euclid(numerator) as i32 + 1,
+            beat: beat.rem_euclid(numerator) as i32 + 1,
+        }
+    }
+}
+
+/// Averages feature values over each beat or bar
+#[derive(Default)]
+pub struct BeatAggregator {
+    period: Option<BeatPosition>,
+    pos_beats: Option<f64>,
+    sums: [f64; MAX_BEAT_FEATURES],
+    features: usize,
+    count: usize,
+}
+
+impl BeatAggregator {
+    /// Drops the running period without sending it, used when the transport stops
+    pub fn clear(&mut self) {
+        self.period = None;
+        self.pos_beats = None;
+        self.count = 0;
+    }
+
+    /// Adds one set of feature values at `pos_beats` to `period`, weighted by the number of
+    /// samples they cover. Once a new period begins the average of the previous one is passed to
+    /// `emit`, a playhead jumping backwards onto the same period also begins a new one.
+    pub fn add<E>(
+        &mut self,
+        pos_beats: f64,
+        period: BeatPosition,
+        values: &[f32],
+        samples: usize,
+        emit: impl FnOnce(BeatPosition, &[f32]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let values = &values[..values.len().min(MAX_BEAT_FEATURES)];
+        let mut closed = None;
+        //Loops and seeks back into the same bar are another pass through it
+        let jumped_back = self
+            .pos_beats
+            .is_some_and(|last| pos_beats < last - POSITION_EPSILON);
+        self.pos_beats = Some(pos_beats);
+        if self.period != Some(period) || jumped_back {
+            if let Some(previous) = self.period.filter(|_| self.count > 0) {
+                let mut averages = [0.0; MAX_BEAT_FEATURES];
+                for (average, sum) in averages.iter_mut().zip(&self.sums) {
+                    *average = (sum / self.count as f64) as f32;
+                }
+                closed = Some((previous, averages, self.features));
+            }
+            self.period = Some(period);
+            self.count = 0;
+        }
+        //Features switched on or off halfway through restart the period
+        if self.features != values.len() {
+            self.features = values.len();
+            self.count = 0;
+        }
+        if self.count == 0 {
+            self.sums = [0.0; MAX_BEAT_FEATURES];
+        }
+        for (sum, value) in self.sums.iter_mut().zip(values) {
+            *sum += *value as f64 * samples as f64;
+        }
+        self.count += samples;
+        match closed {
+            Some((previous, averages, features)) => emit(previous, &averages[..features]),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::f64::consts::PI;
+
+/// Transposed direct form II biquad, coefficients are normalized by a0
+#[derive(Default, Clone, Copy)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    pub fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0,
+            b1,
+            b2,
+            a1,
+            a2,
+            ..Default::default()
+        }
+    }
+
+    pub fn lowpass(frequency: f64, q: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = rbj_terms(frequency, q, sample_rate);
+        let a0 = 1.0 + alpha;
+        Self::new(
+            (1.0 - cos) / 2.0 / a0,
+            (1.0 - cos) / a0,
+            (1.0 - cos) / 2.0 / a0,
+            -2.0 * cos / a0,
+            (1.0 - alpha) / a0,
+        )
+    }
+
+    pub fn highpass(frequency: f64, q: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = rbj_terms(frequency, q, sample_rate);
+        let a0 = 1.0 + alpha;
+        Self::new(
+            (1.0 + cos) / 2.0 / a0,
+            -(1.0 + cos) / a0,
+            (1.0 + cos) / 2.0 / a0,
+            -2.0 * cos / a0,
+            (1.0 - alpha) / a0,
+        )
+    }
+
+    /// Takes the coefficients of `other` but keeps the filter state, so coefficients can change
+    /// while audio is running
+    pub fn set_coefficients(&mut self, other: &Biquad) {
+        *self = Self {
+            z1: self.z1,
+            z2: self.z2,
+            ..*other
+        };
+    }
+
+    pub fn process(&mut self, input: f64) -> f64 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
+
+/// Cosine of the normalized frequency and alpha from the Audio EQ Cookbook
+fn rbj_terms(frequency: f64, q: f64, sample_rate: f64) -> (f64, f64) {
+    //Keep the frequency below nyquist so low sample rates stay stable
+    let omega = 2.0 * PI * frequency.clamp(1.0, sample_rate * 0.49) / sample_rate;
+    (omega.cos(), omega.sin() / (2.0 * q))
+}
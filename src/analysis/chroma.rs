@@ -0,0 +1,129 @@
+use super::spectrum::FFT_SIZE;
+
+pub const PITCH_CLASSES: usize = 12;
+pub const PITCH_CLASS_NAMES: [&str; PITCH_CLASSES] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+const CHROMA_MIN_FREQUENCY: f32 = 100.0;
+const CHROMA_MAX_FREQUENCY: f32 = 5_000.0;
+/// Time constant of the chroma average the key is estimated from
+const KEY_TIME_SECONDS: f32 = 10.0;
+/// Averaged chroma below this is treated as silence and has no key
+const KEY_MIN_ENERGY: f32 = 1e-3;
+/// Spectral peaks quieter than this are ignored, -80 dBFS
+const PEAK_MIN_MAGNITUDE: f32 = 1e-4;
+
+/// Krumhansl-Kessler key profiles, starting from the tonic
+const MAJOR_PROFILE: [f32; PITCH_CLASSES] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f32; PITCH_CLASSES] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Folds the spectral peaks of the spectrum analyzer into pitch classes starting at C, and
+/// estimates the key from their running average
+pub struct ChromaAnalyzer {
+    bin_width: f32,
+    chroma: [f32; PITCH_CLASSES],
+    average: [f32; PITCH_CLASSES],
+}
+
+impl Default for ChromaAnalyzer {
+    fn default() -> Self {
+        Self {
+            bin_width: 44_100.0 / FFT_SIZE as f32,
+            chroma: [0.0; PITCH_CLASSES],
+            average: [0.0; PITCH_CLASSES],
+        }
+    }
+}
+
+impl ChromaAnalyzer {
+    pub fn reset(&mut self, sample_rate: f32) {
+        self.bin_width = sample_rate / FFT_SIZE as f32;
+        self.chroma = [0.0; PITCH_CLASSES];
+        self.average = [0.0; PITCH_CLASSES];
+    }
+
+    /// Takes in a new spectrum, `elapsed` is the time in seconds since the previous one
+    pub fn update(&mut self, magnitudes: &[f32], elapsed: f32) {
+        self.chroma = [0.0; PITCH_CLASSES];
+        //Low notes are closer together than a bin, so each peak is placed at its interpolated
+        //frequency rather than its bin
+        let first = ((CHROMA_MIN_FREQUENCY / self.bin_width) as usize).max(1);
+        let last = ((CHROMA_MAX_FREQUENCY / self.bin_width) as usize).min(magnitudes.len() - 2);
+        for bin in first..=last {
+            let (before, at, after) = (magnitudes[bin - 1], magnitudes[bin], magnitudes[bin + 1]);
+            if at < PEAK_MIN_MAGNITUDE || at <= before || at < after {
+                continue;
+            }
+            let (before_db, at_db, after_db) =
+                (before.max(1e-9).ln(), at.ln(), after.max(1e-9).ln());
+            let curvature = before_db - 2.0 * at_db + after_db;
+            let offset = if curvature < 0.0 {
+                (0.5 * (before_db - after_db) / curvature).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            };
+            let frequency = (bin as f32 + offset) * self.bin_width;
+            let note = 69.0 + 12.0 * (frequency / 440.0).log2();
+            self.chroma[note.round() as usize % PITCH_CLASSES] += at * at;
+        }
+        let coefficient = (-elapsed / KEY_TIME_SECONDS).exp();
+        for (average, energy) in self.average.iter_mut().zip(&self.chroma) {
+            *average = energy + coefficient * (*average - energy);
+        }
+        let max = self
+            .chroma
+            .iter()
+            .fold(0.0f32, |max, energy| max.max(*energy));
+        if max > 0.0 {
+            for energy in &mut self.chroma {
+                *energy /= max;
+            }
+        }
+    }
+
+    /// Energy of each pitch class from C to B, scaled so the strongest is 1.0
+    pub fn chroma(&self) -> &[f32; PITCH_CLASSES] {
+        &self.chroma
+    }
+
+    /// Returns the tonic pitch class and whether the key is minor
+    pub fn key(&self) -> Option<(u8, bool)> {
+        if self.average.iter().sum::<f32>() < KEY_MIN_ENERGY {
+            return None;
+        }
+        let mut best = None;
+        let mut best_correlation = f32::MIN;
+        for tonic in 0..PITCH_CLASSES {
+            for (minor, profile) in [(false, &MAJOR_PROFILE), (true, &MINOR_PROFILE)] {
+                let correlation = correlation(&self.average, profile, tonic);
+                if correlation > best_correlation {
+                    best_correlation = correlation;
+                    best = Some((tonic as u8, minor));
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Pearson correlation of the chroma with the profile rotated to `tonic`
+fn correlation(chroma: &[f32; PITCH_CLASSES], profile: &[f32; PITCH_CLASSES], tonic: usize) -> f32 {
+    let chroma_mean = chroma.iter().sum::<f32>() / PITCH_CLASSES as f32;
+    let profile_mean = profile.iter().sum::<f32>() / PITCH_CLASSES as f32;
+    let (mut covariance, mut chroma_variance, mut profile_variance) = (0.0, 0.0, 0.0);
+    for (class, energy) in chroma.iter().enumerate() {
+        let x = energy - chroma_mean;
+        let y = profile[(class + PITCH_CLASSES - tonic) % PITCH_CLASSES] - profile_mean;
+        covariance += x * y;
+        chroma_variance += x * x;
+        profile_variance += y * y;
+    }
+    covariance
+        / (chroma_variance * profile_variance)
+            .sqrt()
+            .max(f32::EPSILON)
+}
@@ -0,0 +1,105 @@
+use super::biquad::Biquad;
+use super::levels::RmsFollower;
+use super::mono_sample;
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::ops::Range;
+
+pub const MIN_CROSSOVER_BANDS: usize = 3;
+pub const MAX_CROSSOVER_BANDS: usize = 4;
+
+/// Linkwitz-Riley 4th order section, two cascaded Butterworth biquads
+#[derive(Default, Clone, Copy)]
+struct LinkwitzRiley {
+    filters: [Biquad; 2],
+}
+
+impl LinkwitzRiley {
+    fn set(&mut self, coefficients: &Biquad) {
+        for filter in &mut self.filters {
+            filter.set_coefficients(coefficients);
+        }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        let [first, second] = &mut self.filters;
+        second.process(first.process(input))
+    }
+}
+
+/// Splits the mono sum of the input into 3 or 4 bands with Linkwitz-Riley crossovers and
+/// follows the RMS of each band
+#[derive(Default)]
+pub struct Crossover {
+    sample_rate: f32,
+    bands: usize,
+    frequencies: [f32; MAX_CROSSOVER_BANDS - 1],
+    lowpass: [LinkwitzRiley; MAX_CROSSOVER_BANDS - 1],
+    highpass: [LinkwitzRiley; MAX_CROSSOVER_BANDS - 1],
+    rms: [RmsFollower; MAX_CROSSOVER_BANDS],
+}
+
+impl Crossover {
+    pub fn reset(&mut self, sample_rate: f32) {
+        *self = Self {
+            sample_rate,
+            ..Default::default()
+        };
+    }
+
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32, sample_rate: f32) {
+        for follower in &mut self.rms {
+            follower.set_times(attack_ms, release_ms, sample_rate);
+        }
+    }
+
+    /// Sets the band count and the crossover frequencies in hz from low to high, only the first
+    /// `bands - 1` frequencies are used
+    pub fn set_bands(&mut self, bands: usize, frequencies: [f32; MAX_CROSSOVER_BANDS - 1]) {
+        let bands = bands.clamp(MIN_CROSSOVER_BANDS, MAX_CROSSOVER_BANDS);
+        //Crossovers can't overlap, each one is kept at or above the one below it
+        let mut frequencies = frequencies;
+        for i in 1..frequencies.len() {
+            frequencies[i] = frequencies[i].max(frequencies[i - 1]);
+        }
+        if bands == self.bands && frequencies == self.frequencies {
+            return;
+        }
+        self.bands = bands;
+        self.frequencies = frequencies;
+        let sample_rate = self.sample_rate as f64;
+        for (i, frequency) in frequencies.iter().enumerate() {
+            let frequency = *frequency as f64;
+            self.lowpass[i].set(&Biquad::lowpass(frequency, FRAC_1_SQRT_2, sample_rate));
+            self.highpass[i].set(&Biquad::highpass(frequency, FRAC_1_SQRT_2, sample_rate));
+        }
+    }
+
+    pub fn process<C: AsRef<[f32]>>(&mut self, channels: &[C], range: Range<usize>) {
+        if channels.is_empty() || self.bands == 0 {
+            return;
+        }
+        let splits = self.bands - 1;
+        for i in range {
+            //Each crossover takes its band off the bottom and passes the rest up
+            let mut rest = mono_sample(channels, i) as f64;
+            for split in 0..=splits {
+                let band = if split < splits {
+                    let band = self.lowpass[split].process(rest);
+                    rest = self.highpass[split].process(rest);
+                    band
+                } else {
+                    rest
+                } as f32;
+                self.rms[split].process(band);
+            }
+        }
+    }
+
+    pub fn bands(&self) -> usize {
+        self.bands
+    }
+
+    pub fn rms(&self, band: usize) -> f32 {
+        self.rms[band].value()
+    }
+}
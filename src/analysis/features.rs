@@ -0,0 +1,124 @@
+use super::mono_sample;
+use super::spectrum::FFT_SIZE;
+use std::ops::Range;
+
+/// Rolloff is the frequency below which this fraction of the spectral power lies
+const ROLLOFF_FRACTION: f32 = 0.85;
+/// Spectra quieter than this report 0.0 instead of noise driven values
+const SILENCE_POWER: f32 = 1e-12;
+
+/// Spectral descriptors computed from the spectrum analyzer magnitudes, plus the zero
+/// crossing rate of the mono sum
+pub struct SpectralFeatures {
+    previous: Vec<f32>,
+    flux: f32,
+    last_sample: f32,
+    zero_crossings: usize,
+    samples: usize,
+}
+
+impl Default for SpectralFeatures {
+    fn default() -> Self {
+        Self {
+            previous: vec![0.0; FFT_SIZE / 2 + 1],
+            flux: 0.0,
+            last_sample: 0.0,
+            zero_crossings: 0,
+            samples: 0,
+        }
+    }
+}
+
+impl SpectralFeatures {
+    pub fn process<C: AsRef<[f32]>>(&mut self, channels: &[C], range: Range<usize>) {
+        if channels.is_empty() {
+            return;
+        }
+        for i in range {
+            let sample = mono_sample(channels, i);
+            if (sample >= 0.0) != (self.last_sample >= 0.0) {
+                self.zero_crossings += 1;
+            }
+            self.last_sample = sample;
+            self.samples += 1;
+        }
+    }
+
+    /// Takes in a new spectrum, must be called once per analyzed frame for the flux
+    pub fn update(&mut self, magnitudes: &[f32]) {
+        let mut flux = 0.0;
+        for (previous, magnitude) in self.previous.iter_mut().zip(magnitudes) {
+            let rise = (magnitude - *previous).max(0.0);
+            flux += rise * rise;
+            *previous = *magnitude;
+        }
+        self.flux = flux.sqrt();
+    }
+
+    /// Positive spectral change since the previous frame
+    pub fn flux(&self) -> f32 {
+        self.flux
+    }
+
+    /// Fraction of samples (0.0-1.0) that crossed zero since the last call
+    pub fn take_zero_crossing_rate(&mut self) -> f32 {
+        let rate = if self.samples > 0 {
+            self.zero_crossings as f32 / self.samples as f32
+        } else {
+            0.0
+        };
+        self.zero_crossings = 0;
+        self.samples = 0;
+        rate
+    }
+}
+
+/// Magnitude weighted mean frequency in hz
+pub fn centroid(magnitudes: &[f32], bin_width: f32) -> f32 {
+    let total: f32 = magnitudes.iter().sum();
+    if total * total < SILENCE_POWER {
+        return 0.0;
+    }
+    let weighted: f32 = magnitudes
+        .iter()
+        .enumerate()
+        .map(|(bin, magnitude)| bin as f32 * bin_width * magnitude)
+        .sum();
+    weighted / total
+}
+
+/// Geometric over arithmetic mean of the power spectrum, 0.0 for tones up to 1.0 for noise
+pub fn flatness(magnitudes: &[f32]) -> f32 {
+    //DC is left out so offsets don't skew the result
+    let bins = &magnitudes[1.min(magnitudes.len())..];
+    if bins.is_empty() {
+        return 0.0;
+    }
+    let count = bins.len() as f32;
+    let mean = bins.iter().map(|m| m * m).sum::<f32>() / count;
+    if mean < SILENCE_POWER {
+        return 0.0;
+    }
+    let log_mean = bins
+        .iter()
+        .map(|m| (m * m).max(SILENCE_POWER).ln())
+        .sum::<f32>()
+        / count;
+    (log_mean.exp() / mean).clamp(0.0, 1.0)
+}
+
+/// Frequency in hz below which `ROLLOFF_FRACTION` of the power lies
+pub fn rolloff(magnitudes: &[f32], bin_width: f32) -> f32 {
+    let total: f32 = magnitudes.iter().map(|m| m * m).sum();
+    if total < SILENCE_POWER {
+        return 0.0;
+    }
+    let mut cumulative = 0.0;
+    for (bin, magnitude) in magnitudes.iter().enumerate() {
+        cumulative += magnitude * magnitude;
+        if cumulative >= ROLLOFF_FRACTION * total {
+            return bin as f32 * bin_width;
+        }
+    }
+    (magnitudes.len() - 1) as f32 * bin_width
+}
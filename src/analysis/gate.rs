@@ -0,0 +1,99 @@
+use super::levels::EnvelopeFollower;
+use anyhow::Result;
+use std::ops::Range;
+
+pub const GATE_COUNT: usize = 4;
+/// The gates follow a fast peak envelope so a single sample spike doesn't open them
+const GATE_ATTACK_MS: f32 = 1.0;
+const GATE_RELEASE_MS: f32 = 50.0;
+
+/// Settings shared by every gate, thresholds are in dBFS
+pub struct GateSettings {
+    pub count: usize,
+    pub thresholds: [f32; GATE_COUNT],
+    pub hysteresis_db: f32,
+    pub hold_ms: f32,
+}
+
+#[derive(Default, Clone, Copy)]
+struct GateState {
+    open: bool,
+    hold_remaining: usize,
+}
+
+/// Threshold gates on the peak envelope of the input. A gate opens when the level reaches its
+/// threshold and closes once the level has stayed below the threshold minus the hysteresis for
+/// the hold time.
+#[derive(Default)]
+pub struct GateBank {
+    envelope: EnvelopeFollower,
+    sample_rate: f32,
+    gates: [GateState; GATE_COUNT],
+}
+
+impl GateBank {
+    pub fn reset(&mut self, sample_rate: f32) {
+        self.envelope = EnvelopeFollower::default();
+        self.envelope
+            .set_times(GATE_ATTACK_MS, GATE_RELEASE_MS, sample_rate);
+        self.sample_rate = sample_rate;
+        self.gates = [GateState::default(); GATE_COUNT];
+    }
+
+    /// Feeds a run of samples, `emit` is called with the sample index, gate index and new state
+    /// on every transition
+    pub fn process<C: AsRef<[f32]>>(
+        &mut self,
+        channels: &[C],
+        range: Range<usize>,
+        settings: &GateSettings,
+        mut emit: impl FnMut(usize, usize, bool) -> Result<()>,
+    ) -> Result<()> {
+        let count = settings.count.min(GATE_COUNT);
+        //Gates that were switched off close right away instead of hanging open
+        for (gate, state) in self.gates.iter_mut().enumerate().skip(count) {
+            if state.open {
+                state.open = false;
+                emit(range.start, gate, false)?;
+            }
+        }
+        if channels.is_empty() {
+            return Ok(());
+        }
+        let mut open_levels = [0.0; GATE_COUNT];
+        let mut close_levels = [0.0; GATE_COUNT];
+        for gate in 0..count {
+            let threshold = settings.thresholds[gate];
+            open_levels[gate] = db_to_gain(threshold);
+            close_levels[gate] = db_to_gain(threshold - settings.hysteresis_db.max(0.0));
+        }
+        let hold_samples = (settings.hold_ms.max(0.0) * 0.001 * self.sample_rate) as usize;
+        for i in range {
+            let peak = channels
+                .iter()
+                .fold(0.0f32, |peak, channel| peak.max(channel.as_ref()[i].abs()));
+            let level = self.envelope.process(peak);
+            for (gate, state) in self.gates[..count].iter_mut().enumerate() {
+                if !state.open {
+                    if level >= open_levels[gate] {
+                        state.open = true;
+                        state.hold_remaining = hold_samples;
+                        emit(i, gate, true)?;
+                    }
+                } else if level >= close_levels[gate] {
+                    state.hold_remaining = hold_samples;
+                } else if state.hold_remaining > 0 {
+                    state.hold_remaining -= 1;
+                } else {
+                    state.open = false;
+                    emit(i, gate, false)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+/// Averaging time of the mean square before the RMS attack and release are applied
+const RMS_WINDOW_MS: f32 = 50.0;
+
+/// One pole smoother with separate attack and release times
+#[derive(Default, Clone)]
+pub struct EnvelopeFollower {
+    attack: f32,
+    release: f32,
+    value: f32,
+}
+
+impl EnvelopeFollower {
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32, sample_rate: f32) {
+        self.attack = time_coefficient(attack_ms, sample_rate);
+        self.release = time_coefficient(release_ms, sample_rate);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let coefficient = if input > self.value {
+            self.attack
+        } else {
+            self.release
+        };
+        self.value = input + coefficient * (self.value - input);
+        self.value
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+}
+
+fn time_coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+    if time_ms <= 0.0 {
+        0.0
+    } else {
+        (-1000.0 / (time_ms * sample_rate)).exp()
+    }
+}
+
+/// RMS envelope, the mean square is averaged over `RMS_WINDOW_MS` before the attack and
+/// release are applied
+#[derive(Default, Clone)]
+pub struct RmsFollower {
+    mean_square: EnvelopeFollower,
+    rms: EnvelopeFollower,
+}
+
+impl RmsFollower {
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32, sample_rate: f32) {
+        self.mean_square
+            .set_times(RMS_WINDOW_MS, RMS_WINDOW_MS, sample_rate);
+        self.rms.set_times(attack_ms, release_ms, sample_rate);
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let mean_square = self.mean_square.process(input * input);
+        self.rms.process(mean_square.sqrt())
+    }
+
+    pub fn value(&self) -> f32 {
+        self.rms.value()
+    }
+}
+
+/// RMS and peak envelopes for every input channel
+#[derive(Default)]
+pub struct LevelMeter {
+    rms: Vec<RmsFollower>,
+    peak: Vec<EnvelopeFollower>,
+}
+
+impl LevelMeter {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            rms: vec![RmsFollower::default(); channels],
+            peak: vec![EnvelopeFollower::default(); channels],
+        }
+    }
+
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32, sample_rate: f32) {
+        for follower in &mut self.rms {
+            follower.set_times(attack_ms, release_ms, sample_rate);
+        }
+        for follower in &mut self.peak {
+            follower.set_times(attack_ms, release_ms, sample_rate);
+        }
+    }
+
+    pub fn process<C: AsRef<[f32]>>(&mut self, channels: &[C], range: Range<usize>) {
+        for (channel, index) in channels.iter().zip(0..self.rms.len()) {
+            for &sample in &channel.as_ref()[range.clone()] {
+                self.rms[index].process(sample);
+                self.peak[index].process(sample.abs());
+            }
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.rms.len()
+    }
+
+    pub fn rms(&self, channel: usize) -> f32 {
+        self.rms[channel].value()
+    }
+
+    pub fn peak(&self, channel: usize) -> f32 {
+        self.peak[channel].value()
+    }
+}
@@ -0,0 +1,231 @@
+use super::biquad::Biquad;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+/// Loudness and true peak values are clamped to this floor instead of -inf
+pub const LOUDNESS_FLOOR: f32 = -70.0;
+/// Gating blocks are 400ms with 75% overlap, built from 100ms sub blocks
+const SUB_BLOCK_SECONDS: f64 = 0.1;
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+/// Gating blocks quieter than this never count towards the integrated loudness
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Gating blocks are kept in a histogram of 0.1 LU bins so integrated loudness uses fixed
+/// memory for any program length
+const HISTOGRAM_STEP_LU: f64 = 0.1;
+const HISTOGRAM_BINS: usize = 800;
+/// True peak is measured on a 4x oversampled signal
+const OVERSAMPLING: usize = 4;
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// BS.1770 K-weighting, a high shelf followed by the RLB high pass, for any sample rate
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    let f0 = 1_681.974_450_955_533;
+    let gain_db = 3.999_843_853_973_347;
+    let q = 0.707_175_236_955_419_6;
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        (vh + vb * k / q + k * k) / a0,
+        2.0 * (k * k - vh) / a0,
+        (vh - vb * k / q + k * k) / a0,
+        2.0 * (k * k - 1.0) / a0,
+        (1.0 - k / q + k * k) / a0,
+    );
+
+    let f0 = 38.135_470_876_024_44;
+    let q = 0.500_327_037_323_877_3;
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+        1.0,
+        -2.0,
+        1.0,
+        2.0 * (k * k - 1.0) / a0,
+        (1.0 - k / q + k * k) / a0,
+    );
+    [shelf, high_pass]
+}
+
+fn energy_to_loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.max(1e-20).log10()
+}
+
+fn clamp_to_floor(value: f64) -> f32 {
+    (value as f32).max(LOUDNESS_FLOOR)
+}
+
+#[derive(Default, Clone, Copy)]
+struct HistogramBin {
+    count: u64,
+    energy: f64,
+}
+
+/// EBU R128 momentary, short term and integrated loudness plus true peak. Every channel is
+/// weighted equally, which matches BS.1770 for mono and stereo input.
+#[derive(Default)]
+pub struct LoudnessMeter {
+    filters: Vec<[Biquad; 2]>,
+    sub_block_length: usize,
+    sub_block_pos: usize,
+    sub_block_energy: f64,
+    sub_blocks: [f64; SHORT_TERM_SUB_BLOCKS],
+    sub_block_index: usize,
+    sub_blocks_filled: usize,
+    histogram: Vec<HistogramBin>,
+    true_peak_taps: [[f32; TRUE_PEAK_TAPS]; OVERSAMPLING],
+    true_peak_history: Vec<[f32; TRUE_PEAK_TAPS]>,
+    true_peak_pos: usize,
+    true_peak: f32,
+    true_peak_max: f32,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: usize, sample_rate: f32) -> Self {
+        let sample_rate = sample_rate as f64;
+        Self {
+            filters: vec![k_weighting(sample_rate); channels],
+            sub_block_length: ((sample_rate * SUB_BLOCK_SECONDS).round() as usize).max(1),
+            histogram: vec![HistogramBin::default(); HISTOGRAM_BINS],
+            true_peak_taps: true_peak_taps(),
+            true_peak_history: vec![[0.0; TRUE_PEAK_TAPS]; channels],
+            ..Default::default()
+        }
+    }
+
+    /// Restarts the integrated loudness and the maximum true peak
+    pub fn reset_integrated(&mut self) {
+        self.histogram.fill(HistogramBin::default());
+        self.true_peak_max = 0.0;
+    }
+
+    pub fn process<C: AsRef<[f32]>>(&mut self, channels: &[C], range: Range<usize>) {
+        let channel_count = channels.len().min(self.filters.len());
+        if channel_count == 0 {
+            return;
+        }
+        for i in range {
+            for (channel, index) in channels.iter().zip(0..channel_count) {
+                let sample = channel.as_ref()[i];
+                let [shelf, high_pass] = &mut self.filters[index];
+                let weighted = high_pass.process(shelf.process(sample as f64));
+                self.sub_block_energy += weighted * weighted;
+
+                let history = &mut self.true_peak_history[index];
+                history[self.true_peak_pos] = sample;
+                let mut peak = sample.abs();
+                for taps in &self.true_peak_taps {
+                    let mut interpolated = 0.0;
+                    for (tap, offset) in taps.iter().zip(0..TRUE_PEAK_TAPS) {
+                        let pos = (self.true_peak_pos + TRUE_PEAK_TAPS - offset) % TRUE_PEAK_TAPS;
+                        interpolated += tap * history[pos];
+                    }
+                    peak = peak.max(interpolated.abs());
+                }
+                self.true_peak = self.true_peak.max(peak);
+            }
+            self.true_peak_pos = (self.true_peak_pos + 1) % TRUE_PEAK_TAPS;
+
+            self.sub_block_pos += 1;
+            if self.sub_block_pos == self.sub_block_length {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    fn finish_sub_block(&mut self) {
+        self.sub_blocks[self.sub_block_index] =
+            self.sub_block_energy / self.sub_block_length as f64;
+        self.sub_block_index = (self.sub_block_index + 1) % SHORT_TERM_SUB_BLOCKS;
+        self.sub_blocks_filled = (self.sub_blocks_filled + 1).min(SHORT_TERM_SUB_BLOCKS);
+        self.sub_block_pos = 0;
+        self.sub_block_energy = 0.0;
+
+        if self.sub_blocks_filled >= MOMENTARY_SUB_BLOCKS {
+            let energy = self.mean_energy(MOMENTARY_SUB_BLOCKS);
+            let loudness = energy_to_loudness(energy);
+            if loudness >= ABSOLUTE_GATE_LUFS {
+                let bin = ((loudness - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU) as usize;
+                let bin = &mut self.histogram[bin.min(HISTOGRAM_BINS - 1)];
+                bin.count += 1;
+                bin.energy += energy;
+            }
+        }
+    }
+
+    /// Mean energy of the most recent `count` sub blocks
+    fn mean_energy(&self, count: usize) -> f64 {
+        (1..=count)
+            .map(|back| {
+                self.sub_blocks
+                    [(self.sub_block_index + SHORT_TERM_SUB_BLOCKS - back) % SHORT_TERM_SUB_BLOCKS]
+            })
+            .sum::<f64>()
+            / count as f64
+    }
+
+    /// Loudness of the last 400ms in LUFS
+    pub fn momentary(&self) -> f32 {
+        clamp_to_floor(energy_to_loudness(self.mean_energy(MOMENTARY_SUB_BLOCKS)))
+    }
+
+    /// Loudness of the last 3s in LUFS
+    pub fn short_term(&self) -> f32 {
+        clamp_to_floor(energy_to_loudness(self.mean_energy(SHORT_TERM_SUB_BLOCKS)))
+    }
+
+    /// Gated loudness since the last reset in LUFS
+    pub fn integrated(&self) -> f32 {
+        let sum = |bins: &[HistogramBin]| {
+            bins.iter().fold((0, 0.0), |(count, energy), bin| {
+                (count + bin.count, energy + bin.energy)
+            })
+        };
+        let (count, energy) = sum(&self.histogram);
+        if count == 0 {
+            return LOUDNESS_FLOOR;
+        }
+        let relative_gate = energy_to_loudness(energy / count as f64) + RELATIVE_GATE_LU;
+        let first_bin =
+            ((relative_gate - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU).max(0.0) as usize;
+        let (count, energy) = sum(&self.histogram[first_bin.min(HISTOGRAM_BINS - 1)..]);
+        if count == 0 {
+            return LOUDNESS_FLOOR;
+        }
+        clamp_to_floor(energy_to_loudness(energy / count as f64))
+    }
+
+    /// Highest true peak in dBTP since the last call
+    pub fn take_true_peak(&mut self) -> f32 {
+        self.true_peak_max = self.true_peak_max.max(self.true_peak);
+        let peak = std::mem::take(&mut self.true_peak);
+        clamp_to_floor(20.0 * (peak as f64).max(1e-20).log10())
+    }
+
+    /// Highest true peak in dBTP since the last reset
+    pub fn true_peak_max(&self) -> f32 {
+        let peak = self.true_peak_max.max(self.true_peak);
+        clamp_to_floor(20.0 * (peak as f64).max(1e-20).log10())
+    }
+}
+
+/// Polyphase windowed sinc interpolator, one row of taps per oversampled phase
+fn true_peak_taps() -> [[f32; TRUE_PEAK_TAPS]; OVERSAMPLING] {
+    let length = TRUE_PEAK_TAPS * OVERSAMPLING;
+    let center = (length - 1) as f64 / 2.0;
+    let mut taps = [[0.0; TRUE_PEAK_TAPS]; OVERSAMPLING];
+    for i in 0..length {
+        let x = (i as f64 - center) / OVERSAMPLING as f64;
+        let sinc = if x == 0.0 {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        };
+        let window = 0.5 - 0.5 * (2.0 * PI * (i as f64 + 0.5) / length as f64).cos();
+        taps[i % OVERSAMPLING][i / OVERSAMPLING] = (sinc * window) as f32;
+    }
+    taps
+}
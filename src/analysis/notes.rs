@@ -0,0 +1,147 @@
+use anyhow::Result;
+
+/// Pitch has to settle on a note within this time after an onset to start a note
+const NOTE_ONSET_TIMEOUT_MS: f32 = 250.0;
+/// Consecutive pitch estimates that must agree before a note starts or changes
+const NOTE_STABLE_ESTIMATES: usize = 2;
+/// Weaker onsets don't restart a note that is still sounding at the same pitch, decaying notes
+/// produce small onsets of their own
+const NOTE_RETRIGGER_STRENGTH: f32 = 0.3;
+/// A note ends once no pitch estimate has matched it for this long
+const NOTE_RELEASE_MS: f32 = 120.0;
+
+pub enum NoteChange {
+    On { note: u8, velocity: f32 },
+    Off { note: u8 },
+}
+
+/// Turns onsets and pitch estimates into monophonic notes. An onset followed by a stable pitch
+/// starts a note, a new onset, a held change of pitch or losing the pitch ends it.
+/// Times are in samples relative to the start of the current block.
+#[derive(Default)]
+pub struct NoteTracker {
+    onset_timeout: i64,
+    release: i64,
+    pending_onset: Option<(i64, f32)>,
+    note: Option<u8>,
+    velocity: f32,
+    last_match: i64,
+    candidate: Option<(u8, usize)>,
+}
+
+impl NoteTracker {
+    pub fn reset(&mut self, sample_rate: f32) {
+        *self = Self {
+            onset_timeout: (NOTE_ONSET_TIMEOUT_MS * 0.001 * sample_rate) as i64,
+            release: (NOTE_RELEASE_MS * 0.001 * sample_rate) as i64,
+            ..Default::default()
+        };
+    }
+
+    pub fn onset(&mut self, index: usize, strength: f32) {
+        self.pending_onset = Some((index as i64, strength));
+        self.candidate = None;
+    }
+
+    /// Takes a confident pitch estimate, `emit` is called with the sample index of every note
+    /// change
+    pub fn pitch(
+        &mut self,
+        index: usize,
+        frequency: f32,
+        mut emit: impl FnMut(usize, NoteChange) -> Result<()>,
+    ) -> Result<()> {
+        let time = index as i64;
+        let note = (69.0 + 12.0 * (frequency / 440.0).log2()).round();
+        if !(0.0..=127.0).contains(&note) {
+            return Ok(());
+        }
+        let note = note as u8;
+        //Estimates from before the onset still hear the previous note
+        if let Some((onset, _)) = self.pending_onset {
+            if time < onset {
+                return Ok(());
+            }
+        }
+        if self.note == Some(note) {
+            if let Some((_, strength)) = self.pending_onset {
+                if strength < NOTE_RETRIGGER_STRENGTH {
+                    self.pending_onset = None;
+                }
+            }
+        }
+        if self.pending_onset.is_none() && self.note == Some(note) {
+            self.last_match = time;
+            self.candidate = None;
+            return Ok(());
+        }
+        let count = match self.candidate {
+            Some((candidate, count)) if candidate == note => count + 1,
+            _ => 1,
+        };
+        self.candidate = Some((note, count));
+        if count < NOTE_STABLE_ESTIMATES {
+            return Ok(());
+        }
+        //Nothing starts without an onset, a held change while a note sounds is played legato
+        let (start, velocity) = match self.pending_onset.take() {
+            Some((onset, strength)) => (onset.max(0), strength),
+            None if self.note.is_some() => (time, self.velocity),
+            None => return Ok(()),
+        };
+        if let Some(previous) = self.note.take() {
+            emit(start as usize, NoteChange::Off { note: previous })?;
+        }
+        emit(start as usize, NoteChange::On { note, velocity })?;
+        self.note = Some(note);
+        self.velocity = velocity;
+        self.last_match = time;
+        self.candidate = None;
+        Ok(())
+    }
+
+    /// Ends notes that lost their pitch and drops onsets that never found one, `index` is the
+    /// current position in the block
+    pub fn advance(
+        &mut self,
+        index: usize,
+        mut emit: impl FnMut(usize, NoteChange) -> Result<()>,
+    ) -> Result<()> {
+        let time = index as i64;
+        if let Some((onset, _)) = self.pending_onset {
+            if time - onset > self.onset_timeout {
+                self.pending_onset = None;
+            }
+        }
+        if let Some(note) = self.note {
+            if time - self.last_match > self.release {
+                self.note = None;
+                emit(index, NoteChange::Off { note })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Ends the sounding note right away
+    pub fn release(
+        &mut self,
+        index: usize,
+        mut emit: impl FnMut(usize, NoteChange) -> Result<()>,
+    ) -> Result<()> {
+        self.pending_onset = None;
+        self.candidate = None;
+        if let Some(note) = self.note.take() {
+            emit(index, NoteChange::Off { note })?;
+        }
+        Ok(())
+    }
+
+    /// Moves stored times to be relative to the next block
+    pub fn end_block(&mut self, num_samples: usize) {
+        let shift = num_samples as i64;
+        if let Some((onset, _)) = &mut self.pending_onset {
+            *onset -= shift;
+        }
+        self.last_match -= shift;
+    }
+}
@@ -0,0 +1,126 @@
+use super::{mono_sample, WindowedFft};
+use anyhow::Result;
+use std::ops::Range;
+
+const ONSET_FFT_SIZE: usize = 1024;
+/// Samples between flux frames, ~6ms at 44.1khz
+pub const ONSET_HOP_SIZE: usize = 256;
+/// Flux frames averaged for the adaptive threshold, ~0.75s at 44.1khz
+const FLUX_HISTORY: usize = 128;
+/// Flux below this never counts as an onset, keeps noise and silence quiet
+const MIN_FLUX: f32 = 1.0;
+const MIN_ONSET_GAP_MS: f32 = 50.0;
+/// Decay of the running flux peak used to scale onset strength
+const FLUX_PEAK_DECAY: f32 = 0.995;
+
+/// One spectral flux frame, produced every `ONSET_HOP_SIZE` samples
+pub struct OnsetFrame {
+    /// Sample index inside the block the frame ended on
+    pub index: usize,
+    pub flux: f32,
+    /// Strength (0.0-1.0) when the frame is an onset
+    pub onset: Option<f32>,
+}
+
+/// Spectral flux onset detector on the mono sum of the input
+pub struct OnsetDetector {
+    fft: WindowedFft,
+    hop_remaining: usize,
+    previous: Vec<f32>,
+    flux_history: [f32; FLUX_HISTORY],
+    flux_pos: usize,
+    flux_peak: f32,
+    above_threshold: bool,
+    samples_since_onset: usize,
+    min_gap: usize,
+}
+
+impl Default for OnsetDetector {
+    fn default() -> Self {
+        Self {
+            fft: WindowedFft::new(ONSET_FFT_SIZE),
+            previous: vec![0.0; ONSET_FFT_SIZE / 2 + 1],
+            hop_remaining: ONSET_HOP_SIZE,
+            flux_history: [0.0; FLUX_HISTORY],
+            flux_pos: 0,
+            flux_peak: MIN_FLUX,
+            above_threshold: false,
+            samples_since_onset: 0,
+            min_gap: 0,
+        }
+    }
+}
+
+impl OnsetDetector {
+    pub fn reset(&mut self, sample_rate: f32) {
+        self.fft.clear();
+        self.hop_remaining = ONSET_HOP_SIZE;
+        self.previous.fill(0.0);
+        self.flux_history = [0.0; FLUX_HISTORY];
+        self.flux_pos = 0;
+        self.flux_peak = MIN_FLUX;
+        self.above_threshold = false;
+        self.min_gap = (MIN_ONSET_GAP_MS / 1000.0 * sample_rate) as usize;
+        self.samples_since_onset = self.min_gap;
+    }
+
+    /// Feeds a run of samples, `emit` is called with every flux frame. `sensitivity` ranges
+    /// from 0.0 (only strong transients) to 1.0.
+    pub fn process<C: AsRef<[f32]>>(
+        &mut self,
+        channels: &[C],
+        range: Range<usize>,
+        sensitivity: f32,
+        mut emit: impl FnMut(OnsetFrame) -> Result<()>,
+    ) -> Result<()> {
+        if channels.is_empty() {
+            return Ok(());
+        }
+        for i in range {
+            self.fft.push(mono_sample(channels, i));
+            self.samples_since_onset = self.samples_since_onset.saturating_add(1);
+            self.hop_remaining -= 1;
+            if self.hop_remaining == 0 {
+                self.hop_remaining = ONSET_HOP_SIZE;
+                let flux = self.flux()?;
+                let onset = self.detect(flux, sensitivity);
+                emit(OnsetFrame {
+                    index: i,
+                    flux,
+                    onset,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn detect(&mut self, flux: f32, sensitivity: f32) -> Option<f32> {
+        let mean = self.flux_history.iter().sum::<f32>() / FLUX_HISTORY as f32;
+        self.flux_history[self.flux_pos] = flux;
+        self.flux_pos = (self.flux_pos + 1) % FLUX_HISTORY;
+        self.flux_peak = (self.flux_peak * FLUX_PEAK_DECAY).max(flux).max(MIN_FLUX);
+
+        //Sensitivity scales the threshold from 5x down to 1.5x the recent mean flux
+        let threshold = (mean * (1.5 + 3.5 * (1.0 - sensitivity.clamp(0.0, 1.0)))).max(MIN_FLUX);
+        let above_threshold = flux > threshold;
+        let rising = above_threshold && !self.above_threshold;
+        self.above_threshold = above_threshold;
+        if rising && self.samples_since_onset >= self.min_gap {
+            self.samples_since_onset = 0;
+            return Some((flux / self.flux_peak).min(1.0));
+        }
+        None
+    }
+
+    /// Sum of the rises in log magnitude since the previous frame
+    fn flux(&mut self) -> Result<f32> {
+        let bins = self.fft.process()?;
+        let mut flux = 0.0;
+        for (previous, bin) in self.previous.iter_mut().zip(bins) {
+            let magnitude = (1.0 + 100.0 * bin.norm()).ln();
+            flux += (magnitude - *previous).max(0.0);
+            *previous = magnitude;
+        }
+        Ok(flux)
+    }
+}
@@ -0,0 +1,154 @@
+use super::mono_sample;
+use anyhow::Result;
+use std::ops::Range;
+
+/// Input is decimated to roughly this rate before pitch detection to keep YIN affordable
+const PITCH_TARGET_RATE: f32 = 11_025.0;
+/// Decimated samples searched for a period
+const PITCH_WINDOW: usize = 1024;
+/// Decimated samples between pitch estimates, ~23ms
+const PITCH_HOP: usize = 256;
+const PITCH_MIN_FREQUENCY: f32 = 50.0;
+const PITCH_MAX_FREQUENCY: f32 = 1_500.0;
+/// First dip in the normalized difference below this is taken as the period
+const YIN_THRESHOLD: f32 = 0.15;
+
+/// Monophonic YIN pitch tracker on the mono sum of the input
+pub struct PitchDetector {
+    decimation: usize,
+    decimated_rate: f32,
+    accumulator: f32,
+    accumulated: usize,
+    history: Vec<f32>,
+    history_pos: usize,
+    hop_remaining: usize,
+    frame: Vec<f32>,
+    difference: Vec<f32>,
+    min_tau: usize,
+    max_tau: usize,
+}
+
+impl Default for PitchDetector {
+    fn default() -> Self {
+        Self {
+            decimation: 1,
+            decimated_rate: PITCH_TARGET_RATE,
+            accumulator: 0.0,
+            accumulated: 0,
+            history: vec![0.0; PITCH_WINDOW],
+            history_pos: 0,
+            hop_remaining: PITCH_HOP,
+            frame: vec![0.0; PITCH_WINDOW],
+            difference: vec![0.0; PITCH_WINDOW / 2 + 1],
+            min_tau: 2,
+            max_tau: PITCH_WINDOW / 2,
+        }
+    }
+}
+
+impl PitchDetector {
+    pub fn reset(&mut self, sample_rate: f32) {
+        self.decimation = ((sample_rate / PITCH_TARGET_RATE).round() as usize).max(1);
+        self.decimated_rate = sample_rate / self.decimation as f32;
+        self.accumulator = 0.0;
+        self.accumulated = 0;
+        self.history.fill(0.0);
+        self.history_pos = 0;
+        self.hop_remaining = PITCH_HOP;
+        self.min_tau = ((self.decimated_rate / PITCH_MAX_FREQUENCY) as usize).max(2);
+        self.max_tau =
+            ((self.decimated_rate / PITCH_MIN_FREQUENCY).ceil() as usize).min(PITCH_WINDOW / 2);
+    }
+
+    /// Feeds a run of samples, `emit` is called with the sample index, frequency in hz and
+    /// confidence (0.0-1.0) of every estimate at or above `min_confidence`
+    pub fn process<C: AsRef<[f32]>>(
+        &mut self,
+        channels: &[C],
+        range: Range<usize>,
+        min_confidence: f32,
+        mut emit: impl FnMut(usize, f32, f32) -> Result<()>,
+    ) -> Result<()> {
+        if channels.is_empty() {
+            return Ok(());
+        }
+        let gain = 1.0 / self.decimation as f32;
+        for i in range {
+            //Averaging each group of samples doubles as the anti aliasing filter
+            self.accumulator += mono_sample(channels, i);
+            self.accumulated += 1;
+            if self.accumulated < self.decimation {
+                continue;
+            }
+            self.history[self.history_pos] = self.accumulator * gain;
+            self.history_pos = (self.history_pos + 1) % PITCH_WINDOW;
+            self.accumulator = 0.0;
+            self.accumulated = 0;
+            self.hop_remaining -= 1;
+            if self.hop_remaining == 0 {
+                self.hop_remaining = PITCH_HOP;
+                let (frequency, confidence) = self.detect();
+                if confidence >= min_confidence && confidence > 0.0 {
+                    emit(i, frequency, confidence)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn detect(&mut self) -> (f32, f32) {
+        let (newest, oldest) = self.history.split_at(self.history_pos);
+        for (frame, sample) in self.frame.iter_mut().zip(oldest.iter().chain(newest)) {
+            *frame = *sample;
+        }
+
+        //Cumulative mean normalized difference
+        let length = PITCH_WINDOW - self.max_tau;
+        let frame = &self.frame;
+        let difference = &mut self.difference[..=self.max_tau];
+        difference[0] = 1.0;
+        let mut running_sum = 0.0;
+        for tau in 1..=self.max_tau {
+            let delta: f32 = frame[..length]
+                .iter()
+                .zip(&frame[tau..tau + length])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            running_sum += delta;
+            difference[tau] = if running_sum > 0.0 {
+                delta * tau as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        let mut tau = self.min_tau;
+        let mut found = false;
+        while tau < self.max_tau {
+            if difference[tau] < YIN_THRESHOLD {
+                while tau + 1 < self.max_tau && difference[tau + 1] < difference[tau] {
+                    tau += 1;
+                }
+                found = true;
+                break;
+            }
+            tau += 1;
+        }
+        if !found {
+            //No clear period, fall back to the deepest dip which reports a low confidence
+            tau = (self.min_tau..self.max_tau)
+                .min_by(|a, b| difference[*a].total_cmp(&difference[*b]))
+                .unwrap_or(self.min_tau);
+        }
+
+        //Parabolic interpolation between neighbouring lags
+        let (before, at, after) = (difference[tau - 1], difference[tau], difference[tau + 1]);
+        let curvature = before - 2.0 * at + after;
+        let period = if curvature.abs() > f32::EPSILON {
+            tau as f32 + 0.5 * (before - after) / curvature
+        } else {
+            tau as f32
+        };
+        (self.decimated_rate / period, (1.0 - at).clamp(0.0, 1.0))
+    }
+}
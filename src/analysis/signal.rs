@@ -0,0 +1,56 @@
+use anyhow::Result;
+use std::ops::Range;
+
+/// Reports whether the input carries signal. Any sample at or above the threshold marks the
+/// signal present, it is reported gone once every sample has stayed below it for the timeout.
+#[derive(Default)]
+pub struct SignalDetector {
+    sample_rate: f32,
+    present: Option<bool>,
+    timeout_remaining: usize,
+}
+
+impl SignalDetector {
+    pub fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.clear();
+    }
+
+    /// Forgets the reported state, so the next call to `process` reports it again
+    pub fn clear(&mut self) {
+        self.present = None;
+        self.timeout_remaining = 0;
+    }
+
+    /// Feeds a run of samples, `emit` is called with the sample index and the new state on every
+    /// transition
+    pub fn process<C: AsRef<[f32]>>(
+        &mut self,
+        channels: &[C],
+        range: Range<usize>,
+        threshold_db: f32,
+        timeout_ms: f32,
+        mut emit: impl FnMut(usize, bool) -> Result<()>,
+    ) -> Result<()> {
+        let threshold = 10f32.powf(threshold_db / 20.0);
+        let timeout = (timeout_ms.max(0.0) * 0.001 * self.sample_rate) as usize;
+        for i in range {
+            let above = channels
+                .iter()
+                .any(|channel| channel.as_ref()[i].abs() >= threshold);
+            if above {
+                self.timeout_remaining = timeout;
+                if self.present != Some(true) {
+                    self.present = Some(true);
+                    emit(i, true)?;
+                }
+            } else if self.timeout_remaining > 0 {
+                self.timeout_remaining -= 1;
+            } else if self.present != Some(false) {
+                self.present = Some(false);
+                emit(i, false)?;
+            }
+        }
+        Ok(())
+    }
+}
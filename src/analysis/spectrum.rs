@@ -0,0 +1,106 @@
+use super::{mono_sample, WindowedFft};
+use anyhow::Result;
+use std::ops::Range;
+
+/// Length of the analysis window in samples
+pub const FFT_SIZE: usize = 2048;
+/// Most bands a spectrum frame can be reduced to
+pub const MAX_SPECTRUM_BANDS: usize = 32;
+/// Band levels are scaled from this dBFS floor up to 0 dBFS
+const SPECTRUM_FLOOR_DB: f32 = -80.0;
+const SPECTRUM_MIN_FREQUENCY: f32 = 20.0;
+const SPECTRUM_MAX_FREQUENCY: f32 = 20_000.0;
+
+/// Windowed FFT of the mono sum of the input, computed on demand from the most recent
+/// `FFT_SIZE` samples
+pub struct SpectrumAnalyzer {
+    fft: WindowedFft,
+    magnitudes: Vec<f32>,
+    sample_rate: f32,
+    band_count: usize,
+    band_edges: [usize; MAX_SPECTRUM_BANDS + 1],
+}
+
+impl Default for SpectrumAnalyzer {
+    fn default() -> Self {
+        Self {
+            fft: WindowedFft::new(FFT_SIZE),
+            magnitudes: vec![0.0; FFT_SIZE / 2 + 1],
+            sample_rate: 44_100.0,
+            band_count: 0,
+            band_edges: [0; MAX_SPECTRUM_BANDS + 1],
+        }
+    }
+}
+
+impl SpectrumAnalyzer {
+    pub fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.band_count = 0;
+        self.fft.clear();
+        self.magnitudes.fill(0.0);
+    }
+
+    pub fn process<C: AsRef<[f32]>>(&mut self, channels: &[C], range: Range<usize>) {
+        if channels.is_empty() {
+            return;
+        }
+        for i in range {
+            self.fft.push(mono_sample(channels, i));
+        }
+    }
+
+    /// Runs the FFT over the most recent window
+    pub fn analyze(&mut self) -> Result<()> {
+        let bins = self.fft.process()?;
+        for (magnitude, bin) in self.magnitudes.iter_mut().zip(bins) {
+            *magnitude = bin.norm();
+        }
+        Ok(())
+    }
+
+    /// Magnitudes of the last spectrum, a full scale sine reads as 1.0
+    pub fn magnitudes(&self) -> &[f32] {
+        &self.magnitudes
+    }
+
+    /// Width of each magnitude bin in hz
+    pub fn bin_width(&self) -> f32 {
+        self.sample_rate / FFT_SIZE as f32
+    }
+
+    /// Reduces the last spectrum to `count` log spaced bands scaled from 0.0 at the floor
+    /// to 1.0 at 0 dBFS
+    pub fn bands(&mut self, count: usize, bands: &mut [f32]) {
+        let count = count.clamp(1, MAX_SPECTRUM_BANDS).min(bands.len());
+        if count != self.band_count {
+            self.update_band_edges(count);
+        }
+        for (band, edges) in bands[..count].iter_mut().zip(self.band_edges.windows(2)) {
+            let bins = &self.magnitudes[edges[0]..edges[1]];
+            let power: f32 = bins.iter().map(|m| m * m).sum();
+            let db = 10.0 * power.max(1e-12).log10();
+            *band = ((db - SPECTRUM_FLOOR_DB) / -SPECTRUM_FLOOR_DB).clamp(0.0, 1.0);
+        }
+    }
+
+    fn update_band_edges(&mut self, count: usize) {
+        let nyquist = self.sample_rate / 2.0;
+        let max_frequency = SPECTRUM_MAX_FREQUENCY.min(nyquist);
+        let ratio = max_frequency / SPECTRUM_MIN_FREQUENCY;
+        let bin_width = self.sample_rate / FFT_SIZE as f32;
+        let last_bin = FFT_SIZE / 2;
+        let mut low = ((SPECTRUM_MIN_FREQUENCY / bin_width).floor() as usize).max(1);
+        self.band_edges[0] = low;
+        for band in 1..=count {
+            let frequency = SPECTRUM_MIN_FREQUENCY * ratio.powf(band as f32 / count as f32);
+            //Every band gets at least one bin, low bands are narrower than a bin
+            let high = ((frequency / bin_width).round() as usize)
+                .max(low + 1)
+                .min(last_bin + 1);
+            self.band_edges[band] = high;
+            low = high;
+        }
+        self.band_count = count;
+    }
+}
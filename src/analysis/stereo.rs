@@ -0,0 +1,69 @@
+use super::levels::EnvelopeFollower;
+use std::ops::Range;
+
+/// Averaging time of the channel energies, similar to a hardware correlation meter
+const STEREO_WINDOW_MS: f32 = 300.0;
+/// Energies below this are treated as silence and report neutral values
+const SILENCE_ENERGY: f32 = 1e-10;
+
+/// Correlation, balance and width of the first two channels, mono input is treated as a
+/// centered signal
+#[derive(Default)]
+pub struct StereoMeter {
+    left: EnvelopeFollower,
+    right: EnvelopeFollower,
+    product: EnvelopeFollower,
+}
+
+impl StereoMeter {
+    pub fn reset(&mut self, sample_rate: f32) {
+        for follower in [&mut self.left, &mut self.right, &mut self.product] {
+            *follower = EnvelopeFollower::default();
+            follower.set_times(STEREO_WINDOW_MS, STEREO_WINDOW_MS, sample_rate);
+        }
+    }
+
+    pub fn process<C: AsRef<[f32]>>(&mut self, channels: &[C], range: Range<usize>) {
+        let (left, right) = match channels {
+            [] => return,
+            [mono] => (mono.as_ref(), mono.as_ref()),
+            [left, right, ..] => (left.as_ref(), right.as_ref()),
+        };
+        for i in range {
+            self.left.process(left[i] * left[i]);
+            self.right.process(right[i] * right[i]);
+            self.product.process(left[i] * right[i]);
+        }
+    }
+
+    /// Phase correlation from -1.0 (out of phase) through 0.0 (unrelated) to 1.0 (mono)
+    pub fn correlation(&self) -> f32 {
+        let energy = self.left.value() * self.right.value();
+        if energy < SILENCE_ENERGY * SILENCE_ENERGY {
+            return 0.0;
+        }
+        (self.product.value() / energy.sqrt()).clamp(-1.0, 1.0)
+    }
+
+    /// Level balance from -1.0 (left only) to 1.0 (right only)
+    pub fn balance(&self) -> f32 {
+        let left = self.left.value().max(0.0).sqrt();
+        let right = self.right.value().max(0.0).sqrt();
+        if left + right < SILENCE_ENERGY.sqrt() {
+            return 0.0;
+        }
+        (right - left) / (right + left)
+    }
+
+    /// Side share of the mid and side energy, 0.0 for mono, 0.5 for unrelated channels and 1.0
+    /// for out of phase channels
+    pub fn width(&self) -> f32 {
+        let (left, right, product) = (self.left.value(), self.right.value(), self.product.value());
+        let mid = (left + right + 2.0 * product).max(0.0);
+        let side = (left + right - 2.0 * product).max(0.0);
+        if mid + side < SILENCE_ENERGY {
+            return 0.0;
+        }
+        (side / (mid + side)).clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,117 @@
+use super::onset::ONSET_HOP_SIZE;
+
+/// Seconds of onset envelope searched for a beat period
+const TEMPO_HISTORY_SECONDS: f32 = 6.0;
+/// Seconds between estimates
+const TEMPO_INTERVAL_SECONDS: f32 = 1.0;
+const TEMPO_MIN_BPM: f32 = 60.0;
+const TEMPO_MAX_BPM: f32 = 200.0;
+/// Tempos near this are preferred, reduces half and double time guesses
+const TEMPO_PRIOR_BPM: f32 = 120.0;
+/// Width of the tempo preference in octaves
+const TEMPO_PRIOR_OCTAVES: f32 = 1.0;
+
+/// Estimates the tempo by autocorrelating the spectral flux envelope of the onset detector
+#[derive(Default)]
+pub struct TempoEstimator {
+    frame_rate: f32,
+    envelope: Vec<f32>,
+    envelope_pos: usize,
+    envelope_filled: usize,
+    frames_until_estimate: usize,
+    interval: usize,
+    linear: Vec<f32>,
+}
+
+impl TempoEstimator {
+    pub fn reset(&mut self, sample_rate: f32) {
+        self.frame_rate = sample_rate / ONSET_HOP_SIZE as f32;
+        let length = (TEMPO_HISTORY_SECONDS * self.frame_rate) as usize;
+        self.envelope = vec![0.0; length];
+        self.linear = vec![0.0; length];
+        self.envelope_pos = 0;
+        self.envelope_filled = 0;
+        self.interval = ((TEMPO_INTERVAL_SECONDS * self.frame_rate) as usize).max(1);
+        self.frames_until_estimate = self.interval;
+    }
+
+    /// Adds one flux frame, returns the bpm and confidence (0.0-1.0) when an estimate is due
+    pub fn process(&mut self, flux: f32) -> Option<(f32, f32)> {
+        if self.envelope.is_empty() {
+            return None;
+        }
+        self.envelope[self.envelope_pos] = flux;
+        self.envelope_pos = (self.envelope_pos + 1) % self.envelope.len();
+        self.envelope_filled = (self.envelope_filled + 1).min(self.envelope.len());
+        self.frames_until_estimate -= 1;
+        if self.frames_until_estimate > 0 {
+            return None;
+        }
+        self.frames_until_estimate = self.interval;
+        //Wait for enough envelope to hold a few beats at the slowest tempo
+        if self.envelope_filled < self.envelope.len() / 2 {
+            return None;
+        }
+        self.estimate()
+    }
+
+    fn estimate(&mut self) -> Option<(f32, f32)> {
+        let (newest, oldest) = self.envelope.split_at(self.envelope_pos);
+        let length = self.envelope_filled;
+        let recent = oldest
+            .iter()
+            .chain(newest)
+            .skip(self.envelope.len() - length);
+        for (linear, value) in self.linear.iter_mut().zip(recent) {
+            *linear = *value;
+        }
+        let linear = &mut self.linear[..length];
+        let mean = linear.iter().sum::<f32>() / length as f32;
+        for value in linear.iter_mut() {
+            *value -= mean;
+        }
+        let energy: f32 = linear.iter().map(|value| value * value).sum();
+        if energy <= f32::EPSILON {
+            return None;
+        }
+
+        let autocorrelation = |lag: usize| -> f32 {
+            linear[..length - lag]
+                .iter()
+                .zip(&linear[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+                / energy
+        };
+        let min_lag = ((60.0 * self.frame_rate / TEMPO_MAX_BPM).floor() as usize).max(1);
+        let max_lag = ((60.0 * self.frame_rate / TEMPO_MIN_BPM).ceil() as usize).min(length - 2);
+        let mut best_lag = 0;
+        let mut best_score = f32::MIN;
+        for lag in min_lag..=max_lag {
+            let bpm = 60.0 * self.frame_rate / lag as f32;
+            let octaves = (bpm / TEMPO_PRIOR_BPM).log2() / TEMPO_PRIOR_OCTAVES;
+            let score = autocorrelation(lag) * (-0.5 * octaves * octaves).exp();
+            if score > best_score {
+                best_score = score;
+                best_lag = lag;
+            }
+        }
+        if best_lag == 0 {
+            return None;
+        }
+
+        //Parabolic interpolation between neighbouring lags
+        let (before, at, after) = (
+            autocorrelation(best_lag - 1),
+            autocorrelation(best_lag),
+            autocorrelation(best_lag + 1),
+        );
+        let curvature = before - 2.0 * at + after;
+        let lag = if curvature.abs() > f32::EPSILON {
+            best_lag as f32 + (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+        } else {
+            best_lag as f32
+        };
+        Some((60.0 * self.frame_rate / lag, at.clamp(0.0, 1.0)))
+    }
+}
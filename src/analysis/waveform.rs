@@ -0,0 +1,65 @@
+use super::mono_sample;
+use std::ops::Range;
+
+/// Minimum and maximum of every channel since the last report, so peaks between output samples
+/// still show up on a remote scope
+#[derive(Default)]
+pub struct WaveformSummary {
+    min: Vec<f32>,
+    max: Vec<f32>,
+    channels: usize,
+}
+
+impl WaveformSummary {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            min: vec![f32::MAX; channels],
+            max: vec![f32::MIN; channels],
+            channels: 0,
+        }
+    }
+
+    /// With `mono` set the channels are averaged into a single one first
+    pub fn process<C: AsRef<[f32]>>(&mut self, channels: &[C], range: Range<usize>, mono: bool) {
+        if mono {
+            if channels.is_empty() || self.min.is_empty() {
+                return;
+            }
+            for i in range {
+                let sample = mono_sample(channels, i);
+                self.min[0] = self.min[0].min(sample);
+                self.max[0] = self.max[0].max(sample);
+            }
+            self.channels = 1;
+        } else {
+            let count = channels.len().min(self.min.len());
+            for (index, channel) in channels[..count].iter().enumerate() {
+                for &sample in &channel.as_ref()[range.clone()] {
+                    self.min[index] = self.min[index].min(sample);
+                    self.max[index] = self.max[index].max(sample);
+                }
+            }
+            self.channels = count;
+        }
+    }
+
+    /// Fills `values` with a min and max pair per channel and starts the next bucket
+    pub fn take(&mut self, values: &mut Vec<f32>) {
+        values.clear();
+        for index in 0..self.channels {
+            if self.min[index] <= self.max[index] {
+                values.push(self.min[index]);
+                values.push(self.max[index]);
+            } else {
+                values.push(0.0);
+                values.push(0.0);
+            }
+        }
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.min.fill(f32::MAX);
+        self.max.fill(f32::MIN);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::grid::{block_timing, BlockWalker};
+
+/// MIDI clock resolution, pulses per quarter note
+pub const CLOCK_PPQN: f64 = 24.0;
+
+/// Playback starting this close to the beginning sends start instead of continue
+const START_EPSILON: f64 = 1e-6;
+
+pub enum ClockEvent {
+    /// `timing` is the pulse's sample offset inside the block
+    Pulse {
+        timing: u32,
+    },
+    Start,
+    Stop,
+    Continue,
+}
+
+/// Generates 24 PPQN clock pulses and transport messages from the host playhead
+#[derive(Default)]
+pub struct ClockGenerator {
+    was_playing: bool,
+    pulses: BlockWalker,
+}
+
+impl ClockGenerator {
+    /// Emits the transport changes and every pulse that falls inside the current block.
+    /// Pulses are derived from the host position instead of being counted, so loops and
+    /// relocations stay in sync with the host.
+    pub fn process<E>(
+        &mut self,
+        playing: bool,
+        pos_beats: Option<f64>,
+        tempo: Option<f64>,
+        sample_rate: f32,
+        num_samples: usize,
+        mut emit: impl FnMut(ClockEvent) -> Result<(), E>,
+    ) -> Result<(), E> {
+        if playing != self.was_playing {
+            self.was_playing = playing;
+            self.pulses.reset();
+            if !playing {
+                return emit(ClockEvent::Stop);
+            }
+            match pos_beats {
+                Some(pos) if pos.abs() < START_EPSILON => emit(ClockEvent::Start)?,
+                _ => emit(ClockEvent::Continue)?,
+            }
+        }
+        if !playing {
+            return Ok(());
+        }
+
+        let (pos_beats, tempo) = match (pos_beats, tempo) {
+            (Some(pos_beats), Some(tempo)) if tempo > 0.0 => (pos_beats, tempo),
+            _ => return Ok(()),
+        };
+        let samples_per_beat = 60.0 * sample_rate as f64 / tempo;
+        let block_beats = num_samples as f64 / samples_per_beat;
+        let block_start = pos_beats * CLOCK_PPQN;
+        let block_end = (pos_beats + block_beats) * CLOCK_PPQN;
+        self.pulses.points(block_start, block_end, |pulse| {
+            let offset = (pulse as f64 / CLOCK_PPQN - pos_beats) * samples_per_beat;
+            emit(ClockEvent::Pulse {
+                timing: block_timing(offset, num_samples),
+            })
+        })
+    }
+}
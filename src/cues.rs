@@ -0,0 +1,224 @@
+use crate::grid::{block_timing, BlockWalker};
+use rosc::{OscMessage, OscType};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum CueArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+}
+
+/// A message sent when the playhead crosses `bar`:`beat`, both counted from 1
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cue {
+    pub bar: u32,
+    pub beat: f64,
+    pub address: String,
+    pub args: Vec<CueArg>,
+}
+
+impl Cue {
+    pub fn osc_message(&self) -> OscMessage {
+        OscMessage {
+            addr: self.address.clone(),
+            args: self
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    CueArg::Int(value) => OscType::Int(*value),
+                    CueArg::Float(value) => OscType::Float(*value),
+                    CueArg::String(value) => OscType::String(value.clone()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Cue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} {}", self.bar, self.beat, self.address)?;
+        for arg in &self.args {
+            match arg {
+                CueArg::Int(value) => write!(f, " {}", value)?,
+                CueArg::Float(value) if value.fract() == 0.0 => write!(f, " {:.1}", value)?,
+                CueArg::Float(value) => write!(f, " {}", value)?,
+                CueArg::String(value) => write!(f, " {}", value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses cues written as `<bar>:<beat> <address> [args...]`, separated by `;`
+/// e.g. `17:1 /show/scene 3; 33:1 /show/scene 4`
+pub fn parse_cues(text: &str) -> Result<Vec<Cue>, String> {
+    text.split([';', '\n'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_cue)
+        .collect()
+}
+
+fn parse_cue(entry: &str) -> Result<Cue, String> {
+    let mut parts = entry.split_whitespace();
+    let position = parts.next().ok_or("Missing cue position")?;
+    let (bar, beat) = position
+        .split_once(':')
+        .ok_or_else(|| format!("Cue position {} is not <bar>:<beat>", position))?;
+    let bar = match bar.parse::<u32>() {
+        Ok(bar) if bar > 0 => bar,
+        _ => return Err(format!("Invalid cue bar {}", bar)),
+    };
+    let beat = match beat.parse::<f64>() {
+        Ok(beat) if beat >= 1.0 => beat,
+        _ => return Err(format!("Invalid cue beat {}", beat)),
+    };
+    let address = match parts.next() {
+        Some(address) if address.starts_with('/') => address.to_string(),
+        _ => return Err(format!("Cue {} is missing an OSC address", entry)),
+    };
+    let args = parts
+        .map(|arg| {
+            if let Ok(value) = arg.parse::<i32>() {
+                CueArg::Int(value)
+            } else if let Ok(value) = arg.parse::<f32>() {
+                CueArg::Float(value)
+            } else {
+                CueArg::String(arg.to_string())
+            }
+        })
+        .collect();
+    Ok(Cue {
+        bar,
+        beat,
+        address,
+        args,
+    })
+}
+
+pub fn format_cues(cues: &[Cue]) -> String {
+    cues.iter()
+        .map(|cue| cue.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Host playhead information needed to place cues, positions are in quarter notes
+pub struct CuePlayhead {
+    pub pos_beats: f64,
+    pub tempo: f64,
+    pub bar_number: i32,
+    pub bar_start_pos_beats: f64,
+    pub time_sig_numerator: i32,
+    pub time_sig_denominator: i32,
+    pub loop_range_beats: Option<(f64, f64)>,
+}
+
+impl CuePlayhead {
+    fn cue_pos_beats(&self, cue: &Cue) -> f64 {
+        let beat_length = 4.0 / self.time_sig_denominator as f64;
+        let bar_length = self.time_sig_numerator as f64 * beat_length;
+        //bar_number counts from 0 while cues count from 1
+        let bars = cue.bar as f64 - 1.0 - self.bar_number as f64;
+        self.bar_start_pos_beats + bars * bar_length + (cue.beat - 1.0) * beat_length
+    }
+}
+
+/// Fires cues when the playhead crosses them
+#[derive(Default)]
+pub struct CueTrigger {
+    blocks: BlockWalker,
+}
+
+impl CueTrigger {
+    /// Emits the index and sample offset of every cue inside the current block. A block that
+    /// runs past the end of the host loop continues from the loop start.
+    pub fn process<E>(
+        &mut self,
+        cues: &[Cue],
+        playhead: Option<CuePlayhead>,
+        sample_rate: f32,
+        num_samples: usize,
+        mut emit: impl FnMut(usize, u32) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let playhead = match playhead {
+            Some(playhead) if playhead.tempo > 0.0 => playhead,
+            _ => {
+                //Stopped or paused, nothing fires until the playhead moves again
+                self.blocks.reset();
+                return Ok(());
+            }
+        };
+        let samples_per_beat = 60.0 * sample_rate as f64 / playhead.tempo;
+        let block_end = playhead.pos_beats + num_samples as f64 / samples_per_beat;
+
+        //`shift` moves cues from after a loop wrap back to their place in the block
+        let mut fire = |span: Range<f64>, shift: f64| -> Result<(), E> {
+            for (index, cue) in cues.iter().enumerate() {
+                let cue_pos = playhead.cue_pos_beats(cue);
+                if span.contains(&cue_pos) {
+                    let offset = (cue_pos + shift - playhead.pos_beats) * samples_per_beat;
+                    emit(index, block_timing(offset, num_samples))?;
+                }
+            }
+            Ok(())
+        };
+        match playhead.loop_range_beats {
+            Some((loop_start, loop_end))
+                if playhead.pos_beats < loop_end && block_end > loop_end =>
+            {
+                fire(self.blocks.span(playhead.pos_beats, loop_end), 0.0)?;
+                let wrapped_end = loop_start + block_end - loop_end;
+                fire(
+                    self.blocks.span(loop_start, wrapped_end),
+                    loop_end - loop_start,
+                )?;
+            }
+            _ => fire(self.blocks.span(playhead.pos_beats, block_end), 0.0)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cues_with_arguments() {
+        let cues = parse_cues("17:1 /show/scene 3; 33:1 /x 1.5 foo").unwrap();
+        assert_eq!(
+            cues,
+            vec![
+                Cue {
+                    bar: 17,
+                    beat: 1.0,
+                    address: "/show/scene".to_string(),
+                    args: vec![CueArg::Int(3)],
+                },
+                Cue {
+                    bar: 33,
+                    beat: 1.0,
+                    address: "/x".to_string(),
+                    args: vec![CueArg::Float(1.5), CueArg::String("foo".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn formatted_cues_parse_back() {
+        let cues = parse_cues("17:1 /show/scene 3; 33:2.5 /x 1.5 foo").unwrap();
+        assert_eq!(parse_cues(&format_cues(&cues)).unwrap(), cues);
+    }
+
+    #[test]
+    fn rejects_cues_without_an_address() {
+        assert!(parse_cues("17:1 show").is_err());
+        assert!(parse_cues("0:1 /show").is_err());
+        assert!(parse_cues("17 /show").is_err());
+    }
+}
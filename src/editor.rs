@@ -1,12 +1,14 @@
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Sender, TrySendError};
 use nih_plug::debug::*;
 use nih_plug::prelude::Editor;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::ViziaTheming;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use crate::subviews::{ParamView, SettingsView};
+use crate::subviews::{AnalysisView, ParamView, SettingsView, TransportView};
+use crate::cues::{format_cues, parse_cues, Cue};
 use crate::{DawOutParams, OscAddressBaseType, OscChannelMessageType, OscConnectionType};
 
 /// VIZIA uses points instead of pixels for text
@@ -15,6 +17,7 @@ const POINT_SCALE: f32 = 0.75;
 #[derive(Lens)]
 struct DawOutEditor {
     sender: Arc<Sender<OscChannelMessageType>>,
+    cue_sender: Sender<Arc<Vec<Cue>>>,
     params: Arc<DawOutParams>,
     settings: OscSettings,
     log: Vec<String>
@@ -24,14 +27,19 @@ pub struct OscSettings {
     pub osc_server_address: String,
     pub osc_server_port: u16,
     pub osc_address_base: String,
+    pub cues: String,
+    pub capture_path: String,
 }
 
 pub enum DawOutEditorEvent {
     SetOscServerAddress(String),
     SetOscServerPort(u16),
     SetOscAddressBase(String),
+    SetCues(String),
+    SetCapturePath(String),
     ConnectionChange,
     AddressBaseChange,
+    ResetLoudness,
 }
 
 impl Model for DawOutEditor {
@@ -52,6 +60,31 @@ impl Model for DawOutEditor {
                 self.settings.osc_address_base = address.clone();
                 *self.params.osc_address_base.write() = self.settings.osc_address_base.clone();
             }
+            DawOutEditorEvent::SetCues(text) => {
+                nih_trace!("Edit Event {}", text);
+                match parse_cues(text) {
+                    Ok(cues) => {
+                        self.settings.cues = text.clone();
+                        *self.params.cues.write() = cues.clone();
+                        //The audio thread swaps in its own copy, while it isn't running the
+                        //channel can fill up and initialize picks the cues up from the params
+                        match self.cue_sender.try_send(Arc::new(cues)) {
+                            Ok(()) | Err(TrySendError::Full(_)) => {}
+                            Err(e) => nih_error!("Failed to hand over cues {:?}", e),
+                        }
+                        self.log.push("Cues updated".to_string());
+                    }
+                    Err(e) => {
+                        nih_trace!("Invalid cues {}", e);
+                        self.log.push(format!("Invalid cues: {}", e));
+                    }
+                }
+            }
+            DawOutEditorEvent::SetCapturePath(path) => {
+                nih_trace!("Edit Event {}", path);
+                self.settings.capture_path = path.clone();
+                *self.params.capture_path.write() = self.settings.capture_path.clone();
+            }
             DawOutEditorEvent::ConnectionChange => {
                 nih_trace!(
                     "Connection Changed {}:{}",
@@ -83,18 +116,24 @@ impl Model for DawOutEditor {
                     self.log.push(format!("Failed to update base address"));
                 }
             }
+            DawOutEditorEvent::ResetLoudness => {
+                nih_trace!("Integrated Loudness Reset");
+                self.log.push("Integrated loudness reset".to_string());
+                self.params.loudness_reset.store(true, Ordering::Release);
+            }
         });
     }
 }
 
 // Makes sense to also define this here, makes it a bit easier to keep track of
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    ViziaState::new(|| (700, 400))
+    ViziaState::new(|| (1400, 450))
 }
 
 pub(crate) fn create(
     params: Arc<DawOutParams>,
     sender: Arc<Sender<OscChannelMessageType>>,
+    cue_sender: Sender<Arc<Vec<Cue>>>,
     editor_state: Arc<ViziaState>,
 ) -> Option<Box<dyn Editor>> {
     create_vizia_editor(editor_state, ViziaTheming::Custom, move |cx, _| {
@@ -103,12 +142,15 @@ pub(crate) fn create(
 
         DawOutEditor {
             sender: sender.clone(),
+            cue_sender: cue_sender.clone(),
             params: params.clone(),
             log: Vec::new(),
             settings: OscSettings {
                 osc_server_address: params.osc_server_address.read().to_string(),
                 osc_server_port: *params.osc_server_port.read(),
-                osc_address_base: params.osc_address_base.read().to_string()
+                osc_address_base: params.osc_address_base.read().to_string(),
+                cues: format_cues(&params.cues.read()),
+                capture_path: params.capture_path.read().to_string(),
             }.into()
         }
         .build(cx);
@@ -124,6 +166,8 @@ pub(crate) fn create(
                 .class("title");
             HStack::new(cx, |cx| {
                 SettingsView::new(cx, DawOutEditor::settings, DawOutEditor::params, DawOutEditor::log);
+                TransportView::new(cx, DawOutEditor::settings, DawOutEditor::params);
+                AnalysisView::new(cx, DawOutEditor::params);
                 ParamView::new(cx, DawOutEditor::params);
             });
         });
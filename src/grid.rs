@@ -0,0 +1,90 @@
+use std::ops::Range;
+
+/// Tolerance for rounding in the host position between consecutive blocks
+const POSITION_EPSILON: f64 = 1e-6;
+
+/// Follows the host position from block to block so events on the boundary between two blocks
+/// are sent exactly once. Positions can be in any unit, clock pulses, timecode frames or beats.
+#[derive(Default)]
+pub struct BlockWalker {
+    last_end: Option<f64>,
+}
+
+impl BlockWalker {
+    /// Forgets the previous block, used when the transport stops
+    pub fn reset(&mut self) {
+        self.last_end = None;
+    }
+
+    /// Range covered by the block from `start` to `end`. Rounding in the host position can leave
+    /// consecutive blocks overlapping or a hair apart, the block then continues where the
+    /// previous one ended.
+    pub fn span(&mut self, start: f64, end: f64) -> Range<f64> {
+        let start = match self.last_end {
+            Some(last_end) if (start - last_end).abs() < POSITION_EPSILON => last_end,
+            //A playhead landing a hair after an event still sends it
+            _ => start - POSITION_EPSILON,
+        };
+        self.last_end = Some(end);
+        start..end
+    }
+
+    /// Emits every whole numbered position inside the block from `start` to `end`
+    pub fn points<E>(
+        &mut self,
+        start: f64,
+        end: f64,
+        mut emit: impl FnMut(i64) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let span = self.span(start, end);
+        let mut point = span.start.ceil() as i64;
+        while (point as f64) < span.end {
+            emit(point)?;
+            point += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Sample offset of an event `offset` samples into the block, kept inside the block
+pub fn block_timing(offset: f64, num_samples: usize) -> u32 {
+    (offset.max(0.0).round() as u32).min(num_samples.saturating_sub(1) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(walker: &mut BlockWalker, start: f64, end: f64) -> Vec<i64> {
+        let mut points = Vec::new();
+        walker
+            .points(start, end, |point| -> Result<(), ()> {
+                points.push(point);
+                Ok(())
+            })
+            .unwrap();
+        points
+    }
+
+    #[test]
+    fn boundary_point_is_sent_once() {
+        let mut walker = BlockWalker::default();
+        assert_eq!(points(&mut walker, 0.5, 1.0), Vec::<i64>::new());
+        assert_eq!(points(&mut walker, 1.0, 1.5), vec![1]);
+    }
+
+    #[test]
+    fn boundary_point_is_sent_once_with_rounding() {
+        let mut walker = BlockWalker::default();
+        assert_eq!(points(&mut walker, 0.5, 1.0 + 1e-9), vec![1]);
+        assert_eq!(points(&mut walker, 1.0 - 1e-9, 1.5), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn point_at_the_start_of_a_new_block_is_sent() {
+        let mut walker = BlockWalker::default();
+        assert_eq!(points(&mut walker, 1.0, 1.5), vec![1]);
+        walker.reset();
+        assert_eq!(points(&mut walker, 1.0, 1.5), vec![1]);
+    }
+}
@@ -1,30 +1,94 @@
-use anyhow::Result;
+use analysis::beats::{BeatAggregator, BeatPosition, MAX_BEAT_FEATURES};
+use analysis::chroma::{ChromaAnalyzer, PITCH_CLASS_NAMES};
+use analysis::crossover::{Crossover, MAX_CROSSOVER_BANDS, MIN_CROSSOVER_BANDS};
+use analysis::features::{self, SpectralFeatures};
+use analysis::gate::{GateBank, GateSettings, GATE_COUNT};
+use analysis::levels::LevelMeter;
+use analysis::loudness::LoudnessMeter;
+use analysis::notes::{NoteChange, NoteTracker};
+use analysis::onset::OnsetDetector;
+use analysis::pitch::PitchDetector;
+use analysis::signal::SignalDetector;
+use analysis::spectrum::{SpectrumAnalyzer, MAX_SPECTRUM_BANDS};
+use analysis::stereo::StereoMeter;
+use analysis::tempo::TempoEstimator;
+use analysis::waveform::WaveformSummary;
+use analysis::ReportClock;
+use anyhow::{anyhow, Result};
+use clock::{ClockEvent, ClockGenerator};
 use crossbeam_channel::{Receiver, Sender};
+use cues::{Cue, CuePlayhead, CueTrigger};
 use nih_plug::debug::*;
 use nih_plug::prelude::*;
+use nih_plug::wrapper::state::{ParamValue, PluginState};
 use nih_plug_vizia::ViziaState;
 use parking_lot::RwLock;
-use rosc::{OscMessage, OscPacket, OscType};
-use rubato::{FftFixedOut, Resampler};
+use pool::FloatPool;
+use resampler::AudioResampler;
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::net::UdpSocket;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use timecode::{Timecode, TimecodeGenerator, TimecodeRate};
 
+mod analysis;
+mod clock;
+mod cues;
 mod editor;
+mod grid;
+mod pool;
+mod resampler;
 mod subviews;
+mod timecode;
 
 pub struct DawOut {
     params: Arc<DawOutParams>,
-    osc_thread: Option<JoinHandle<()>>,
+    osc_thread: Option<JoinHandle<Receiver<OscChannelMessageType>>>,
     sender: Arc<Sender<OscChannelMessageType>>,
     receiver: Option<Receiver<OscChannelMessageType>>,
     editor_state: Arc<ViziaState>,
     input_sample_rate: f32,
-    resampler: Option<FftFixedOut<f32>>,
-    resampler_buffer: Option<Vec<Vec<f32>>>,
+    max_buffer_size: u32,
+    process_mode: ProcessMode,
+    rendered_samples: u64,
+    input_channels: usize,
+    sidechain_channels: usize,
+    resampler: Option<Box<AudioResampler>>,
+    resampler_sender: Sender<Box<AudioResampler>>,
+    resampler_receiver: Receiver<Box<AudioResampler>>,
+    cues: Arc<Vec<Cue>>,
+    cue_sender: Sender<Arc<Vec<Cue>>>,
+    cue_receiver: Receiver<Arc<Vec<Cue>>>,
+    report_clock: ReportClock,
+    level_meter: LevelMeter,
+    loudness: LoudnessMeter,
+    stereo: StereoMeter,
+    crossover: Crossover,
+    spectrum: SpectrumAnalyzer,
+    features: SpectralFeatures,
+    chroma: ChromaAnalyzer,
+    sent_key: Option<(u8, bool)>,
+    onset: OnsetDetector,
+    pitch: PitchDetector,
+    tempo: TempoEstimator,
+    notes: NoteTracker,
+    gates: GateBank,
+    signal: SignalDetector,
+    beat_features: BeatAggregator,
+    float_pool: FloatPool,
+    audio_pool: FloatPool,
+    audio_frames: u32,
+    waveform_clock: ReportClock,
+    waveform: WaveformSummary,
+    clock: ClockGenerator,
+    timecode: TimecodeGenerator,
+    cue_trigger: CueTrigger,
     p1_dirty: Arc<AtomicBool>,
     p2_dirty: Arc<AtomicBool>,
     p3_dirty: Arc<AtomicBool>,
@@ -33,6 +97,9 @@ pub struct DawOut {
     p6_dirty: Arc<AtomicBool>,
     p7_dirty: Arc<AtomicBool>,
     p8_dirty: Arc<AtomicBool>,
+    timetag_dirty: Arc<AtomicBool>,
+    resampler_dirty: Arc<AtomicBool>,
+    loudness_reset: Arc<AtomicBool>,
 }
 
 impl Default for DawOut {
@@ -45,8 +112,13 @@ impl Default for DawOut {
         let p6_dirty = Arc::new(AtomicBool::new(false));
         let p7_dirty = Arc::new(AtomicBool::new(false));
         let p8_dirty = Arc::new(AtomicBool::new(false));
+        let timetag_dirty = Arc::new(AtomicBool::new(false));
+        let resampler_dirty = Arc::new(AtomicBool::new(false));
+        let loudness_reset = Arc::new(AtomicBool::new(false));
 
         let channel = OscChannel::default();
+        let (resampler_sender, resampler_receiver) = crossbeam_channel::bounded(8);
+        let (cue_sender, cue_receiver) = crossbeam_channel::bounded(8);
         Self {
             params: Arc::new(DawOutParams::new(
                 p1_dirty.clone(),
@@ -57,13 +129,49 @@ impl Default for DawOut {
                 p6_dirty.clone(),
                 p7_dirty.clone(),
                 p8_dirty.clone(),
+                timetag_dirty.clone(),
+                resampler_dirty.clone(),
+                loudness_reset.clone(),
             )),
             osc_thread: None,
             sender: Arc::new(channel.sender),
             receiver: Some(channel.receiver),
             input_sample_rate: 1.0,
+            max_buffer_size: 0,
+            process_mode: ProcessMode::Realtime,
+            rendered_samples: 0,
+            input_channels: 0,
+            sidechain_channels: 0,
             resampler: None,
-            resampler_buffer: None,
+            resampler_sender,
+            resampler_receiver,
+            cues: Arc::default(),
+            cue_sender,
+            cue_receiver,
+            report_clock: ReportClock::default(),
+            level_meter: LevelMeter::default(),
+            loudness: LoudnessMeter::default(),
+            stereo: StereoMeter::default(),
+            crossover: Crossover::default(),
+            spectrum: SpectrumAnalyzer::default(),
+            features: SpectralFeatures::default(),
+            chroma: ChromaAnalyzer::default(),
+            sent_key: None,
+            onset: OnsetDetector::default(),
+            pitch: PitchDetector::default(),
+            tempo: TempoEstimator::default(),
+            notes: NoteTracker::default(),
+            gates: GateBank::default(),
+            signal: SignalDetector::default(),
+            beat_features: BeatAggregator::default(),
+            float_pool: FloatPool::new(FLOAT_POOL_BUFFERS, FLOAT_POOL_CAPACITY),
+            audio_pool: FloatPool::new(AUDIO_POOL_BUFFERS, AUDIO_BLOCK_MAX_SAMPLES),
+            audio_frames: 0,
+            waveform_clock: ReportClock::default(),
+            waveform: WaveformSummary::default(),
+            clock: ClockGenerator::default(),
+            timecode: TimecodeGenerator::default(),
+            cue_trigger: CueTrigger::default(),
             editor_state: editor::default_state(),
             p1_dirty,
             p2_dirty,
@@ -73,16 +181,42 @@ impl Default for DawOut {
             p6_dirty,
             p7_dirty,
             p8_dirty,
+            timetag_dirty,
+            resampler_dirty,
+            loudness_reset,
         }
     }
 }
 
+pub enum DawOutTask {
+    /// Builds a resampler for a new OSC sample rate or audio source off the audio thread
+    BuildResampler {
+        input_sample_rate: usize,
+        output_sample_rate: usize,
+        channels: usize,
+    },
+    /// Frees a replaced resampler off the audio thread
+    DropResampler(Box<AudioResampler>),
+    /// Frees a replaced cue list off the audio thread
+    DropCues(Arc<Vec<Cue>>),
+}
+
 impl Drop for DawOut {
     fn drop(&mut self) {
         self.kill_background_thread();
     }
 }
 
+/// Buffers kept for messages with a variable number of float arguments
+const FLOAT_POOL_BUFFERS: usize = 1024;
+/// Most float arguments a pooled message can hold
+const FLOAT_POOL_CAPACITY: usize = MAX_SPECTRUM_BANDS;
+/// Buffers kept for audio blocks
+const AUDIO_POOL_BUFFERS: usize = 64;
+/// Most samples in one audio block message, longer resampled blocks are split so every message
+/// fits in a UDP datagram
+const AUDIO_BLOCK_MAX_SAMPLES: usize = 1024;
+
 struct OscChannel {
     sender: Sender<OscChannelMessageType>,
     receiver: Receiver<OscChannelMessageType>,
@@ -104,10 +238,246 @@ struct OscNoteType {
     channel: u8,
     note: u8,
     velocity: f32,
+    offset: f32,
+}
+
+impl OscNoteType {
+    /// Notes detected from audio are sent like MIDI notes on the first channel
+    fn from_change(change: NoteChange, offset: f32) -> OscChannelMessageType {
+        match change {
+            NoteChange::On { note, velocity } => OscChannelMessageType::NoteOn(Self {
+                channel: 0,
+                note,
+                velocity,
+                offset,
+            }),
+            NoteChange::Off { note } => OscChannelMessageType::NoteOff(Self {
+                channel: 0,
+                note,
+                velocity: 0.0,
+                offset,
+            }),
+        }
+    }
+}
+
+struct OscClockType {
+    offset: f32,
+}
+
+/// Block mode makes rates this high practical, analysis is still capped at `MAX_REPORT_RATE`
+const MAX_OSC_SAMPLE_RATE: f32 = 48_000.0;
+
+/// Per sample audio sends a message for every frame, higher rates would fill the channel
+const MAX_PER_SAMPLE_RATE: usize = 1_000;
+
+/// Most channels sent in a single audio message
+const MAX_AUDIO_CHANNELS: usize = 8;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum AudioChannelMode {
+    #[name = "Left"]
+    Left,
+    #[name = "Right"]
+    Right,
+    #[name = "Mono Sum"]
+    MonoSum,
+    #[name = "All Channels"]
+    AllChannels,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum AudioSource {
+    #[name = "Main"]
+    Main,
+    #[name = "Sidechain"]
+    Sidechain,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum BeatPeriod {
+    #[name = "Beat"]
+    Beat,
+    #[name = "Bar"]
+    Bar,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum AudioFormat {
+    #[name = "Per Sample"]
+    Samples,
+    #[name = "Float Block"]
+    FloatBlock,
+    #[name = "Blob Block"]
+    BlobBlock,
+    #[name = "Waveform"]
+    Waveform,
 }
 
 struct OscAudioType {
+    values: [f32; MAX_AUDIO_CHANNELS],
+    channels: u8,
+    offset: f32,
+}
+
+impl OscAudioType {
+    fn from_frame(
+        mode: AudioChannelMode,
+        resampled: &[Vec<f32>],
+        frame: usize,
+        offset: f32,
+    ) -> Self {
+        let mut values = [0.0; MAX_AUDIO_CHANNELS];
+        let channels = match mode {
+            AudioChannelMode::Left => {
+                values[0] = resampled[0][frame];
+                1
+            }
+            AudioChannelMode::Right => {
+                //Mono input falls back to its only channel
+                values[0] = resampled[resampled.len().min(2) - 1][frame];
+                1
+            }
+            AudioChannelMode::MonoSum => {
+                values[0] = analysis::mono_sample(resampled, frame);
+                1
+            }
+            AudioChannelMode::AllChannels => {
+                for (value, channel) in values.iter_mut().zip(resampled) {
+                    *value = channel[frame];
+                }
+                resampled.len().min(MAX_AUDIO_CHANNELS)
+            }
+        };
+        Self {
+            values,
+            channels: channels as u8,
+            offset,
+        }
+    }
+}
+
+/// One meter value per channel
+#[derive(Default)]
+struct OscLevelType {
+    values: [f32; MAX_AUDIO_CHANNELS],
+    channels: u8,
+    offset: f32,
+}
+
+struct OscAudioBlockType {
+    /// Interleaved frames
+    values: Vec<f32>,
+    /// Resampled frames sent before this block, wraps around
+    frame: u32,
+    sample_rate: u32,
+    channels: u8,
+    blob: bool,
+    offset: f32,
+}
+
+struct OscTimecodeType {
+    timecode: Timecode,
+    offset: f32,
+}
+
+struct OscCueType {
+    /// The list the cue fired from, edits made since then don't change what is sent
+    cues: Arc<Vec<Cue>>,
+    index: usize,
+    offset: f32,
+}
+
+struct OscOnsetType {
+    strength: f32,
+    offset: f32,
+}
+
+struct OscPitchType {
+    frequency: f32,
+    confidence: f32,
+    offset: f32,
+}
+
+struct OscGateType {
+    index: u8,
+    open: bool,
+    offset: f32,
+}
+
+struct OscSignalType {
+    present: bool,
+    offset: f32,
+}
+
+struct OscBeatFeaturesType {
+    position: BeatPosition,
+    values: [f32; MAX_BEAT_FEATURES],
+    count: u8,
+    offset: f32,
+}
+
+struct OscBpmDetectedType {
+    bpm: f32,
+    confidence: f32,
+    offset: f32,
+}
+
+enum LoudnessMeasurement {
+    Momentary,
+    ShortTerm,
+    Integrated,
+    TruePeak,
+    TruePeakMax,
+}
+
+struct OscLoudnessType {
+    measurement: LoudnessMeasurement,
+    value: f32,
+    offset: f32,
+}
+
+enum StereoMeasurement {
+    Correlation,
+    Balance,
+    Width,
+}
+
+struct OscStereoType {
+    measurement: StereoMeasurement,
+    value: f32,
+    offset: f32,
+}
+
+enum SpectralFeature {
+    Centroid,
+    Flatness,
+    Rolloff,
+    Flux,
+    ZeroCrossingRate,
+}
+
+struct OscFeatureType {
+    feature: SpectralFeature,
     value: f32,
+    offset: f32,
+}
+
+struct OscKeyType {
+    tonic: u8,
+    minor: bool,
+    offset: f32,
+}
+
+struct OscFloatsType {
+    values: Vec<f32>,
+    offset: f32,
+}
+
+struct OscBandsType {
+    values: [f32; MAX_CROSSOVER_BANDS],
+    bands: u8,
+    offset: f32,
 }
 
 struct OscConnectionType {
@@ -119,16 +489,90 @@ struct OscAddressBaseType {
     address: String,
 }
 
+struct OscTimetagType {
+    latency: Option<f32>,
+}
+
+struct OscCaptureType {
+    path: Option<String>,
+}
+
+/// Sent at the start of every block, the messages that follow are timed from it
+struct OscSongPositionType {
+    seconds: f64,
+    /// Wall clock time the block was processed at
+    time: SystemTime,
+}
+
 
 
 enum OscChannelMessageType {
     Exit,
     ConnectionChange(OscConnectionType),
     AddressBaseChange(OscAddressBaseType),
+    TimetagChange(OscTimetagType),
+    CaptureChange(OscCaptureType),
+    SongPosition(OscSongPositionType),
     Param(OscParamType),
     NoteOn(OscNoteType),
     NoteOff(OscNoteType),
     Audio(OscAudioType),
+    AudioBlock(OscAudioBlockType),
+    Clock(OscClockType),
+    Start,
+    Stop,
+    Continue,
+    Timecode(OscTimecodeType),
+    Cue(OscCueType),
+    LevelRms(OscLevelType),
+    LevelPeak(OscLevelType),
+    Spectrum(OscFloatsType),
+    Waveform(OscFloatsType),
+    Bands(OscBandsType),
+    Onset(OscOnsetType),
+    Pitch(OscPitchType),
+    BpmDetected(OscBpmDetectedType),
+    Gate(OscGateType),
+    Signal(OscSignalType),
+    BeatFeatures(OscBeatFeaturesType),
+    Loudness(OscLoudnessType),
+    LoudnessReset,
+    Stereo(OscStereoType),
+    Feature(OscFeatureType),
+    Chroma(OscFloatsType),
+    Key(OscKeyType),
+}
+
+impl OscChannelMessageType {
+    /// Seconds between the start of the block and the event inside it
+    fn offset(&self) -> f32 {
+        match self {
+            OscChannelMessageType::NoteOn(message) => message.offset,
+            OscChannelMessageType::NoteOff(message) => message.offset,
+            OscChannelMessageType::Audio(message) => message.offset,
+            OscChannelMessageType::AudioBlock(message) => message.offset,
+            OscChannelMessageType::Clock(message) => message.offset,
+            OscChannelMessageType::Timecode(message) => message.offset,
+            OscChannelMessageType::Cue(message) => message.offset,
+            OscChannelMessageType::LevelRms(message) => message.offset,
+            OscChannelMessageType::LevelPeak(message) => message.offset,
+            OscChannelMessageType::Spectrum(message) => message.offset,
+            OscChannelMessageType::Waveform(message) => message.offset,
+            OscChannelMessageType::Bands(message) => message.offset,
+            OscChannelMessageType::Onset(message) => message.offset,
+            OscChannelMessageType::Pitch(message) => message.offset,
+            OscChannelMessageType::BpmDetected(message) => message.offset,
+            OscChannelMessageType::Gate(message) => message.offset,
+            OscChannelMessageType::Signal(message) => message.offset,
+            OscChannelMessageType::BeatFeatures(message) => message.offset,
+            OscChannelMessageType::Loudness(message) => message.offset,
+            OscChannelMessageType::Stereo(message) => message.offset,
+            OscChannelMessageType::Feature(message) => message.offset,
+            OscChannelMessageType::Chroma(message) => message.offset,
+            OscChannelMessageType::Key(message) => message.offset,
+            _ => 0.0,
+        }
+    }
 }
 
 #[derive(Params)]
@@ -140,14 +584,139 @@ pub struct DawOutParams {
     osc_server_port: RwLock<u16>,
     #[persist = "osc_address_base"]
     osc_address_base: RwLock<String>,
+    #[persist = "cues"]
+    cues: RwLock<Vec<Cue>>,
+    #[persist = "capture_path"]
+    capture_path: RwLock<String>,
 
     //Setting Flags
     #[id = "flag_send_midi"]
     flag_send_midi: BoolParam,
     #[id = "flag_send_audio"]
     flag_send_audio: BoolParam,
+    #[id = "audio_channel_mode"]
+    audio_channel_mode: EnumParam<AudioChannelMode>,
+    #[id = "audio_format"]
+    audio_format: EnumParam<AudioFormat>,
+    #[id = "audio_source"]
+    audio_source: EnumParam<AudioSource>,
+    #[id = "flag_send_levels"]
+    flag_send_levels: BoolParam,
+    #[id = "levels_source"]
+    levels_source: EnumParam<AudioSource>,
+    #[id = "level_attack_ms"]
+    level_attack_ms: FloatParam,
+    #[id = "level_release_ms"]
+    level_release_ms: FloatParam,
+    #[id = "flag_send_loudness"]
+    flag_send_loudness: BoolParam,
+    #[id = "loudness_source"]
+    loudness_source: EnumParam<AudioSource>,
+    #[id = "flag_send_stereo"]
+    flag_send_stereo: BoolParam,
+    #[id = "stereo_source"]
+    stereo_source: EnumParam<AudioSource>,
+    #[id = "flag_send_spectrum"]
+    flag_send_spectrum: BoolParam,
+    #[id = "spectrum_bands"]
+    spectrum_bands: IntParam,
+    #[id = "spectrum_source"]
+    spectrum_source: EnumParam<AudioSource>,
+    #[id = "flag_send_bands"]
+    flag_send_bands: BoolParam,
+    #[id = "crossover_bands"]
+    crossover_bands: IntParam,
+    #[id = "crossover_low_hz"]
+    crossover_low_hz: FloatParam,
+    #[id = "crossover_mid_hz"]
+    crossover_mid_hz: FloatParam,
+    #[id = "crossover_high_hz"]
+    crossover_high_hz: FloatParam,
+    #[id = "bands_source"]
+    bands_source: EnumParam<AudioSource>,
+    #[id = "flag_send_centroid"]
+    flag_send_centroid: BoolParam,
+    #[id = "flag_send_flatness"]
+    flag_send_flatness: BoolParam,
+    #[id = "flag_send_rolloff"]
+    flag_send_rolloff: BoolParam,
+    #[id = "flag_send_flux"]
+    flag_send_flux: BoolParam,
+    #[id = "flag_send_zcr"]
+    flag_send_zcr: BoolParam,
+    #[id = "flag_send_chroma"]
+    flag_send_chroma: BoolParam,
+    #[id = "flag_send_key"]
+    flag_send_key: BoolParam,
+    #[id = "flag_send_onset"]
+    flag_send_onset: BoolParam,
+    #[id = "onset_sensitivity"]
+    onset_sensitivity: FloatParam,
+    #[id = "onset_source"]
+    onset_source: EnumParam<AudioSource>,
+    #[id = "flag_send_pitch"]
+    flag_send_pitch: BoolParam,
+    #[id = "pitch_confidence"]
+    pitch_confidence: FloatParam,
+    #[id = "pitch_source"]
+    pitch_source: EnumParam<AudioSource>,
+    #[id = "flag_send_bpm"]
+    flag_send_bpm: BoolParam,
+    #[id = "flag_send_audio_notes"]
+    flag_send_audio_notes: BoolParam,
+    #[id = "flag_send_gates"]
+    flag_send_gates: BoolParam,
+    #[id = "gate_count"]
+    gate_count: IntParam,
+    #[id = "gate1_threshold"]
+    gate1_threshold: FloatParam,
+    #[id = "gate2_threshold"]
+    gate2_threshold: FloatParam,
+    #[id = "gate3_threshold"]
+    gate3_threshold: FloatParam,
+    #[id = "gate4_threshold"]
+    gate4_threshold: FloatParam,
+    #[id = "gate_hysteresis"]
+    gate_hysteresis: FloatParam,
+    #[id = "gate_hold_ms"]
+    gate_hold_ms: FloatParam,
+    #[id = "gate_source"]
+    gate_source: EnumParam<AudioSource>,
+    #[id = "flag_send_signal"]
+    flag_send_signal: BoolParam,
+    #[id = "signal_threshold"]
+    signal_threshold: FloatParam,
+    #[id = "signal_timeout_ms"]
+    signal_timeout_ms: FloatParam,
+    #[id = "signal_source"]
+    signal_source: EnumParam<AudioSource>,
+    #[id = "flag_send_beat_features"]
+    flag_send_beat_features: BoolParam,
+    #[id = "beat_features_period"]
+    beat_features_period: EnumParam<BeatPeriod>,
+    #[id = "beat_feature_rms"]
+    beat_feature_rms: BoolParam,
+    #[id = "beat_feature_bands"]
+    beat_feature_bands: BoolParam,
+    #[id = "beat_feature_centroid"]
+    beat_feature_centroid: BoolParam,
+    #[id = "flag_send_clock"]
+    flag_send_clock: BoolParam,
+    #[id = "flag_send_timecode"]
+    flag_send_timecode: BoolParam,
+    #[id = "timecode_rate"]
+    timecode_rate: EnumParam<TimecodeRate>,
     #[id = "osc_sample_rate"]
-    osc_sample_rate: IntParam,
+    osc_sample_rate: FloatParam,
+    #[id = "flag_send_timetags"]
+    flag_send_timetags: BoolParam,
+    #[id = "flag_latency_from_buffer"]
+    flag_latency_from_buffer: BoolParam,
+    #[id = "osc_latency_ms"]
+    osc_latency_ms: IntParam,
+
+    //Set by the editor, cleared by the audio thread
+    loudness_reset: Arc<AtomicBool>,
 
     //Exposed Params
     #[id = "param1"]
@@ -179,25 +748,386 @@ impl DawOutParams {
         p6_dirty: Arc<AtomicBool>,
         p7_dirty: Arc<AtomicBool>,
         p8_dirty: Arc<AtomicBool>,
+        timetag_dirty: Arc<AtomicBool>,
+        resampler_dirty: Arc<AtomicBool>,
+        loudness_reset: Arc<AtomicBool>,
     ) -> Self {
+        let timetag_flag_dirty = timetag_dirty.clone();
+        let latency_flag_dirty = timetag_dirty.clone();
+        let source_resampler_dirty = resampler_dirty.clone();
+        let format_resampler_dirty = resampler_dirty.clone();
         Self {
             osc_server_address: RwLock::new("127.0.0.1".to_string()),
             osc_server_port: RwLock::new(9000),
             osc_address_base: RwLock::new("daw-out".to_string()),
+            cues: RwLock::new(Vec::new()),
+            capture_path: RwLock::new(
+                std::env::temp_dir()
+                    .join("daw-out-capture.osc")
+                    .to_string_lossy()
+                    .to_string(),
+            ),
             flag_send_midi: BoolParam::new("flag_send_midi", true)
                 .hide()
                 .non_automatable(),
             flag_send_audio: BoolParam::new("flag_send_audio", false)
                 .hide()
                 .non_automatable(),
-            //TODO: handle value change updating resampler ratio
-            osc_sample_rate: IntParam::new(
+            audio_channel_mode: EnumParam::new("audio_channel_mode", AudioChannelMode::Left)
+                .hide()
+                .non_automatable(),
+            audio_format: EnumParam::new("audio_format", AudioFormat::Samples)
+                .hide()
+                .non_automatable()
+                .with_callback(Arc::new(move |_x| {
+                    format_resampler_dirty.store(true, Ordering::Release)
+                })),
+            audio_source: EnumParam::new("audio_source", AudioSource::Main)
+                .hide()
+                .non_automatable()
+                .with_callback(Arc::new(move |_x| {
+                    source_resampler_dirty.store(true, Ordering::Release)
+                })),
+            flag_send_levels: BoolParam::new("flag_send_levels", false)
+                .hide()
+                .non_automatable(),
+            levels_source: EnumParam::new("levels_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            level_attack_ms: FloatParam::new(
+                "level_attack_ms",
+                10.0,
+                FloatRange::Linear { min: 0.0, max: 500.0 },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            level_release_ms: FloatParam::new(
+                "level_release_ms",
+                300.0,
+                FloatRange::Linear { min: 0.0, max: 2000.0 },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            flag_send_loudness: BoolParam::new("flag_send_loudness", false)
+                .hide()
+                .non_automatable(),
+            loudness_source: EnumParam::new("loudness_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_stereo: BoolParam::new("flag_send_stereo", false)
+                .hide()
+                .non_automatable(),
+            stereo_source: EnumParam::new("stereo_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_spectrum: BoolParam::new("flag_send_spectrum", false)
+                .hide()
+                .non_automatable(),
+            spectrum_bands: IntParam::new(
+                "spectrum_bands",
+                16,
+                IntRange::Linear {
+                    min: 2,
+                    max: MAX_SPECTRUM_BANDS as i32,
+                },
+            )
+            .hide()
+            .non_automatable(),
+            spectrum_source: EnumParam::new("spectrum_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_bands: BoolParam::new("flag_send_bands", false)
+                .hide()
+                .non_automatable(),
+            crossover_bands: IntParam::new(
+                "crossover_bands",
+                MIN_CROSSOVER_BANDS as i32,
+                IntRange::Linear {
+                    min: MIN_CROSSOVER_BANDS as i32,
+                    max: MAX_CROSSOVER_BANDS as i32,
+                },
+            )
+            .hide()
+            .non_automatable(),
+            crossover_low_hz: FloatParam::new(
+                "crossover_low_hz",
+                200.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_step_size(1.0)
+            .hide()
+            .non_automatable(),
+            crossover_mid_hz: FloatParam::new(
+                "crossover_mid_hz",
+                2_000.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_step_size(1.0)
+            .hide()
+            .non_automatable(),
+            crossover_high_hz: FloatParam::new(
+                "crossover_high_hz",
+                8_000.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_step_size(1.0)
+            .hide()
+            .non_automatable(),
+            bands_source: EnumParam::new("bands_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_centroid: BoolParam::new("flag_send_centroid", false)
+                .hide()
+                .non_automatable(),
+            flag_send_flatness: BoolParam::new("flag_send_flatness", false)
+                .hide()
+                .non_automatable(),
+            flag_send_rolloff: BoolParam::new("flag_send_rolloff", false)
+                .hide()
+                .non_automatable(),
+            flag_send_flux: BoolParam::new("flag_send_flux", false)
+                .hide()
+                .non_automatable(),
+            flag_send_zcr: BoolParam::new("flag_send_zcr", false)
+                .hide()
+                .non_automatable(),
+            flag_send_chroma: BoolParam::new("flag_send_chroma", false)
+                .hide()
+                .non_automatable(),
+            flag_send_key: BoolParam::new("flag_send_key", false)
+                .hide()
+                .non_automatable(),
+            flag_send_onset: BoolParam::new("flag_send_onset", false)
+                .hide()
+                .non_automatable(),
+            onset_sensitivity: FloatParam::new(
+                "onset_sensitivity",
+                0.5,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_step_size(0.01)
+            .hide()
+            .non_automatable(),
+            onset_source: EnumParam::new("onset_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_pitch: BoolParam::new("flag_send_pitch", false)
+                .hide()
+                .non_automatable(),
+            pitch_confidence: FloatParam::new(
+                "pitch_confidence",
+                0.8,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_step_size(0.01)
+            .hide()
+            .non_automatable(),
+            pitch_source: EnumParam::new("pitch_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_bpm: BoolParam::new("flag_send_bpm", false)
+                .hide()
+                .non_automatable(),
+            flag_send_audio_notes: BoolParam::new("flag_send_audio_notes", false)
+                .hide()
+                .non_automatable(),
+            flag_send_gates: BoolParam::new("flag_send_gates", false)
+                .hide()
+                .non_automatable(),
+            gate_count: IntParam::new(
+                "gate_count",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: GATE_COUNT as i32,
+                },
+            )
+            .hide()
+            .non_automatable(),
+            gate1_threshold: FloatParam::new(
+                "gate1_threshold",
+                -40.0,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            gate2_threshold: FloatParam::new(
+                "gate2_threshold",
+                -30.0,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            gate3_threshold: FloatParam::new(
+                "gate3_threshold",
+                -20.0,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            gate4_threshold: FloatParam::new(
+                "gate4_threshold",
+                -10.0,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            gate_hysteresis: FloatParam::new(
+                "gate_hysteresis",
+                3.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            gate_hold_ms: FloatParam::new(
+                "gate_hold_ms",
+                100.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 2000.0,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(1.0)
+            .hide()
+            .non_automatable(),
+            gate_source: EnumParam::new("gate_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_signal: BoolParam::new("flag_send_signal", false)
+                .hide()
+                .non_automatable(),
+            signal_threshold: FloatParam::new(
+                "signal_threshold",
+                -60.0,
+                FloatRange::Linear {
+                    min: -120.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1)
+            .hide()
+            .non_automatable(),
+            signal_timeout_ms: FloatParam::new(
+                "signal_timeout_ms",
+                2000.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 30_000.0,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(10.0)
+            .hide()
+            .non_automatable(),
+            signal_source: EnumParam::new("signal_source", AudioSource::Main)
+                .hide()
+                .non_automatable(),
+            flag_send_beat_features: BoolParam::new("flag_send_beat_features", false)
+                .hide()
+                .non_automatable(),
+            beat_features_period: EnumParam::new("beat_features_period", BeatPeriod::Beat)
+                .hide()
+                .non_automatable(),
+            beat_feature_rms: BoolParam::new("beat_feature_rms", true)
+                .hide()
+                .non_automatable(),
+            beat_feature_bands: BoolParam::new("beat_feature_bands", false)
+                .hide()
+                .non_automatable(),
+            beat_feature_centroid: BoolParam::new("beat_feature_centroid", false)
+                .hide()
+                .non_automatable(),
+            flag_send_clock: BoolParam::new("flag_send_clock", false)
+                .hide()
+                .non_automatable(),
+            flag_send_timecode: BoolParam::new("flag_send_timecode", false)
+                .hide()
+                .non_automatable(),
+            timecode_rate: EnumParam::new("timecode_rate", TimecodeRate::Fps25)
+                .hide()
+                .non_automatable(),
+            osc_sample_rate: FloatParam::new(
                 "osc_sample_rate",
-                100,
+                100.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: MAX_OSC_SAMPLE_RATE,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz")
+            .with_step_size(1.0)
+            .hide()
+            .non_automatable()
+            .with_callback(Arc::new(move |_x| resampler_dirty.store(true, Ordering::Release))),
+            flag_send_timetags: BoolParam::new("flag_send_timetags", false)
+                .hide()
+                .non_automatable()
+                .with_callback(Arc::new(move |_x| {
+                    timetag_flag_dirty.store(true, Ordering::Release)
+                })),
+            flag_latency_from_buffer: BoolParam::new("flag_latency_from_buffer", false)
+                .hide()
+                .non_automatable()
+                .with_callback(Arc::new(move |_x| {
+                    latency_flag_dirty.store(true, Ordering::Release)
+                })),
+            osc_latency_ms: IntParam::new(
+                "osc_latency_ms",
+                0,
                 IntRange::Linear { min: 0, max: 1000 },
             )
+            .with_unit(" ms")
             .hide()
-            .non_automatable(),
+            .non_automatable()
+            .with_callback(Arc::new(move |_x| timetag_dirty.store(true, Ordering::Release))),
+            loudness_reset,
             param1: FloatParam::new("param1", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
                 .with_step_size(0.01)
                 .with_callback(Arc::new(move |_x| p1_dirty.store(true, Ordering::Release))),
@@ -226,6 +1156,20 @@ impl DawOutParams {
     }
 }
 
+/// Audio passes through unchanged, plus the stereo sidechain used as an analysis source
+const fn audio_layout(channels: u32) -> AudioIOLayout {
+    AudioIOLayout {
+        main_input_channels: NonZeroU32::new(channels),
+        main_output_channels: NonZeroU32::new(channels),
+        aux_input_ports: &[new_nonzero_u32(2)],
+        aux_output_ports: &[],
+        names: PortNames {
+            aux_inputs: &["Sidechain"],
+            ..PortNames::const_default()
+        },
+    }
+}
+
 impl Plugin for DawOut {
     const NAME: &'static str = "DAW Out";
     const VENDOR: &'static str = "gamingrobot";
@@ -237,69 +1181,143 @@ impl Plugin for DawOut {
     const MIDI_INPUT: MidiConfig = MidiConfig::Basic;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
     const SAMPLE_ACCURATE_AUTOMATION: bool = true;
-    const HARD_REALTIME_ONLY: bool = true;
+    const HARD_REALTIME_ONLY: bool = false;
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
-
-        aux_input_ports: &[],
-        aux_output_ports: &[],
-        names: PortNames::const_default(),
-    }];
+    //Stereo first so hosts without layout negotiation pick it
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        audio_layout(2),
+        audio_layout(1),
+        audio_layout(4),
+        audio_layout(6),
+        audio_layout(8),
+        //MIDI only
+        AudioIOLayout {
+            main_input_channels: None,
+            main_output_channels: None,
+            aux_input_ports: &[],
+            aux_output_ports: &[],
+            names: PortNames::const_default(),
+        },
+    ];
 
     type SysExMessage = ();
-    type BackgroundTask = ();
+    type BackgroundTask = DawOutTask;
 
     fn params(&self) -> Arc<dyn Params> {
         nih_trace!("Params Called");
         self.params.clone() as Arc<dyn Params>
     }
 
+    fn filter_state(state: &mut PluginState) {
+        //The OSC sample rate used to be an int param
+        if let Some(ParamValue::I32(sample_rate)) = state.params.get("osc_sample_rate") {
+            let sample_rate = *sample_rate as f32;
+            state
+                .params
+                .insert("osc_sample_rate".to_string(), ParamValue::F32(sample_rate));
+        }
+    }
+
     fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
         nih_trace!("Editor Called");
         editor::create(
             self.params.clone(),
             self.sender.clone(),
+            self.cue_sender.clone(),
             self.editor_state.clone(),
         )
     }
 
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let resampler_sender = self.resampler_sender.clone();
+        Box::new(move |task| match task {
+            DawOutTask::BuildResampler {
+                input_sample_rate,
+                output_sample_rate,
+                channels,
+            } => match AudioResampler::new(input_sample_rate, output_sample_rate, channels) {
+                Ok(resampler) => {
+                    nih_trace!("Rebuilt resampler for {} Hz", output_sample_rate);
+                    if let Err(e) = resampler_sender.try_send(Box::new(resampler)) {
+                        nih_error!("Failed to hand over rebuilt resampler {:?}", e);
+                    }
+                }
+                Err(e) => nih_error!("Failed to rebuild resampler {:?}", e),
+            },
+            DawOutTask::DropResampler(resampler) => drop(resampler),
+            DawOutTask::DropCues(cues) => drop(cues),
+        })
+    }
+
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
         nih_trace!("Initialize Called");
 
-        if buffer_config.process_mode != ProcessMode::Realtime {
-            nih_log!("Plugin is not in realtime mode, bailing!");
-            return false;
-        }
+        self.process_mode = buffer_config.process_mode;
+        self.rendered_samples = 0;
+        self.audio_frames = 0;
 
         //Setup resampler
         self.input_sample_rate = buffer_config.sample_rate;
-        self.resampler = match FftFixedOut::<f32>::new(
-            self.input_sample_rate as usize / 100, //TODO: is this right?
-            self.params.osc_sample_rate.value() as usize,
-            100,
-            2,
-            2,
-        ) {
-            Ok(sampler) => Some(sampler),
-            Err(e) => {
-                nih_error!(
-                    "Failed to create resampler, audio processing will be disabled {:?}",
-                    e
-                );
-                None
+        self.max_buffer_size = buffer_config.max_buffer_size;
+        //Latency derived from the buffer size may have changed
+        self.timetag_dirty.store(true, Ordering::Release);
+        self.input_channels = audio_io_layout
+            .main_input_channels
+            .map_or(0, NonZeroU32::get) as usize;
+        self.sidechain_channels = audio_io_layout
+            .aux_input_ports
+            .first()
+            .map_or(0, |channels| channels.get()) as usize;
+        //Meters cover whichever input has more channels so sources can be switched at any time
+        let analysis_channels = self.input_channels.max(self.sidechain_channels);
+        self.level_meter = LevelMeter::new(analysis_channels);
+        self.loudness = LoudnessMeter::new(analysis_channels, self.input_sample_rate);
+        self.waveform = WaveformSummary::new(analysis_channels);
+        self.stereo.reset(self.input_sample_rate);
+        self.crossover.reset(self.input_sample_rate);
+        self.spectrum.reset(self.input_sample_rate);
+        self.chroma.reset(self.input_sample_rate);
+        self.sent_key = None;
+        self.onset.reset(self.input_sample_rate);
+        self.pitch.reset(self.input_sample_rate);
+        self.tempo.reset(self.input_sample_rate);
+        self.notes.reset(self.input_sample_rate);
+        self.gates.reset(self.input_sample_rate);
+        self.signal.reset(self.input_sample_rate);
+        self.beat_features.clear();
+
+        //Rebuilds queued for the previous sample rate are stale
+        while self.resampler_receiver.try_recv().is_ok() {}
+        self.resampler_dirty.store(false, Ordering::Release);
+        let resampler_channels = self.source_channels(self.params.audio_source.value());
+        self.resampler = if resampler_channels == 0 {
+            nih_trace!("No audio channels, audio sending will be disabled");
+            None
+        } else {
+            match AudioResampler::new(
+                self.input_sample_rate as usize,
+                self.audio_sample_rate(),
+                resampler_channels,
+            ) {
+                Ok(resampler) => Some(Box::new(resampler)),
+                Err(e) => {
+                    nih_error!(
+                        "Failed to create resampler, audio processing will be disabled {:?}",
+                        e
+                    );
+                    None
+                }
             }
         };
 
-        if let Some(resampler) = &self.resampler {
-            self.resampler_buffer = Some(resampler.output_buffer_allocate(true));
-        }
+        //The audio thread keeps its own copy of the cues, the editor hands over edits
+        while self.cue_receiver.try_recv().is_ok() {}
+        self.cues = Arc::new(self.params.cues.read().clone());
 
         //Setup OSC background thread
         //Dont remake the background thread if its already running
@@ -324,7 +1342,10 @@ impl Plugin for DawOut {
                     ip_port,
                     connect_result.unwrap_err()
                 );
-                return false;
+                //Offline renders are captured to a file and never use the socket
+                if buffer_config.process_mode != ProcessMode::Offline {
+                    return false;
+                }
             }
             nih_trace!("Connected!");
             nih_trace!("Connected to: {}", ip_port);
@@ -333,8 +1354,11 @@ impl Plugin for DawOut {
             nih_trace!("OSC Address Base: {}", address_base);
 
             if let Some(receiver) = std::mem::replace(&mut self.receiver, None) {
-                let client_thread =
-                    thread::spawn(move || osc_client_worker(socket, address_base, receiver));
+                let float_pool = self.float_pool.clone();
+                let audio_pool = self.audio_pool.clone();
+                let client_thread = thread::spawn(move || {
+                    osc_client_worker(socket, address_base, float_pool, audio_pool, receiver)
+                });
 
                 self.osc_thread = Some(client_thread);
             } else {
@@ -369,6 +1393,24 @@ impl Plugin for DawOut {
                 );
             }
         }
+
+        //Offline renders are captured to a file instead of being sent to the socket
+        let capture_path = match buffer_config.process_mode {
+            ProcessMode::Offline => Some(self.params.capture_path.read().to_string()),
+            _ => None,
+        };
+        nih_trace!("Capture Path: {:?}", capture_path);
+        let capture_send_result = self
+            .sender
+            .send(OscChannelMessageType::CaptureChange(OscCaptureType {
+                path: capture_path,
+            }));
+        if capture_send_result.is_err() {
+            nih_error!(
+                "Failed to send CaptureChange update {:?}",
+                capture_send_result.unwrap_err()
+            );
+        }
         true
     }
 
@@ -380,14 +1422,43 @@ impl Plugin for DawOut {
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
+        aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        //Process Song Position
+        let position_result = self.process_song_position(context.transport(), buffer.samples());
+        if position_result.is_err() {
+            nih_error!(
+                "Failed to send song position {:?}",
+                position_result.unwrap_err()
+            );
+        }
         //Process Dirty Params
         let param_result = self.process_params();
         if param_result.is_err() {
             nih_error!("Failed to send params {:?}", param_result.unwrap_err());
         }
+        //Process Clock
+        if self.params.flag_send_clock.value() {
+            let clock_result = self.process_clock(context.transport(), buffer.samples());
+            if clock_result.is_err() {
+                nih_error!("Failed to process Clock {:?}", clock_result.unwrap_err());
+            }
+        }
+        //Process Timecode
+        if self.params.flag_send_timecode.value() {
+            let timecode_result = self.process_timecode(context.transport(), buffer.samples());
+            if timecode_result.is_err() {
+                nih_error!("Failed to process Timecode {:?}", timecode_result.unwrap_err());
+            }
+        }
+        //Process Cue Changes
+        self.process_cue_list(context);
+        //Process Cues
+        let cue_result = self.process_cues(context.transport(), buffer.samples());
+        if cue_result.is_err() {
+            nih_error!("Failed to process Cues {:?}", cue_result.unwrap_err());
+        }
         //Process Note Events
         if self.params.flag_send_midi.value() {
             while let Some(event) = context.next_event() {
@@ -401,13 +1472,20 @@ impl Plugin for DawOut {
                 }
             }
         }
+        //Process Resampler Changes
+        self.process_resampler(context);
         //Process Audio Events
         if self.params.flag_send_audio.value() {
-            let audio_result = self.process_audio_buffer(buffer);
+            let audio_result = self.process_audio_buffer(buffer, aux);
             if audio_result.is_err() {
                 nih_error!("Failed to process Audio {:?}", audio_result.unwrap_err());
             }
         }
+        //Process Audio Analysis
+        let analysis_result = self.process_analysis(buffer, aux, context.transport());
+        if analysis_result.is_err() {
+            nih_error!("Failed to process Analysis {:?}", analysis_result.unwrap_err());
+        }
         ProcessStatus::Normal
     }
 }
@@ -422,6 +1500,27 @@ impl DawOut {
         self.send_dirty_param(&self.p6_dirty, &self.params.param6)?;
         self.send_dirty_param(&self.p7_dirty, &self.params.param7)?;
         self.send_dirty_param(&self.p8_dirty, &self.params.param8)?;
+        self.send_dirty_timetag()?;
+        Ok(())
+    }
+
+    fn send_dirty_timetag(&self) -> Result<()> {
+        if self
+            .timetag_dirty
+            .compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            let latency = if !self.params.flag_send_timetags.value() {
+                None
+            } else if self.params.flag_latency_from_buffer.value() {
+                Some(self.max_buffer_size as f32 / self.input_sample_rate)
+            } else {
+                Some(self.params.osc_latency_ms.value() as f32 / 1000.0)
+            };
+            nih_trace!("Timetag Dirty: {:?}", latency);
+            self.sender
+                .send(OscChannelMessageType::TimetagChange(OscTimetagType { latency }))?;
+        }
         Ok(())
     }
 
@@ -443,7 +1542,7 @@ impl DawOut {
     fn process_event(&self, event: &NoteEvent<()>) -> Result<()> {
         match *event {
             NoteEvent::NoteOn {
-                timing: _,
+                timing,
                 channel,
                 note,
                 velocity,
@@ -454,9 +1553,10 @@ impl DawOut {
                     channel,
                     note,
                     velocity,
+                    offset: timing as f32 / self.input_sample_rate,
                 }))?,
             NoteEvent::NoteOff {
-                timing: _,
+                timing,
                 channel,
                 note,
                 velocity,
@@ -467,62 +1567,882 @@ impl DawOut {
                     channel,
                     note,
                     velocity,
+                    offset: timing as f32 / self.input_sample_rate,
                 }))?,
             _ => {}
         };
         Ok(())
     }
 
-    fn process_audio_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
+    fn process_song_position(&mut self, transport: &Transport, num_samples: usize) -> Result<()> {
+        let seconds = transport
+            .pos_seconds()
+            .unwrap_or(self.rendered_samples as f64 / self.input_sample_rate as f64);
+        self.rendered_samples += num_samples as u64;
+        self.sender
+            .send(OscChannelMessageType::SongPosition(OscSongPositionType {
+                seconds,
+                time: SystemTime::now(),
+            }))?;
+        Ok(())
+    }
+
+    fn process_clock(&mut self, transport: &Transport, num_samples: usize) -> Result<()> {
+        let sender = &self.sender;
+        let sample_rate = self.input_sample_rate;
+        self.clock.process(
+            transport.playing,
+            transport.pos_beats(),
+            transport.tempo,
+            self.input_sample_rate,
+            num_samples,
+            |event| {
+                sender.send(match event {
+                    ClockEvent::Pulse { timing } => OscChannelMessageType::Clock(OscClockType {
+                        offset: timing as f32 / sample_rate,
+                    }),
+                    ClockEvent::Start => OscChannelMessageType::Start,
+                    ClockEvent::Stop => OscChannelMessageType::Stop,
+                    ClockEvent::Continue => OscChannelMessageType::Continue,
+                })
+            },
+        )?;
+        Ok(())
+    }
+
+    fn process_timecode(&mut self, transport: &Transport, num_samples: usize) -> Result<()> {
+        let sender = &self.sender;
+        let sample_rate = self.input_sample_rate;
+        self.timecode.process(
+            transport.playing,
+            transport.pos_seconds(),
+            self.params.timecode_rate.value(),
+            sample_rate,
+            num_samples,
+            |timecode, timing| {
+                sender.send(OscChannelMessageType::Timecode(OscTimecodeType {
+                    timecode,
+                    offset: timing as f32 / sample_rate,
+                }))
+            },
+        )?;
+        Ok(())
+    }
+
+    fn process_cue_list(&mut self, context: &mut impl ProcessContext<Self>) {
+        while let Ok(cues) = self.cue_receiver.try_recv() {
+            //Swap in the edited cues, the old list is freed by the background thread
+            let retired = std::mem::replace(&mut self.cues, cues);
+            context.execute_background(DawOutTask::DropCues(retired));
+        }
+    }
+
+    fn process_cues(&mut self, transport: &Transport, num_samples: usize) -> Result<()> {
+        let playhead = match (transport.playing, transport.pos_beats(), transport.tempo) {
+            (true, Some(pos_beats), Some(tempo)) => {
+                let (bar_number, bar_start_pos_beats) =
+                    match (transport.bar_number(), transport.bar_start_pos_beats()) {
+                        (Some(bar_number), Some(bar_start)) => (bar_number, bar_start),
+                        _ => (0, 0.0),
+                    };
+                Some(CuePlayhead {
+                    pos_beats,
+                    tempo,
+                    bar_number,
+                    bar_start_pos_beats,
+                    time_sig_numerator: transport.time_sig_numerator.unwrap_or(4),
+                    time_sig_denominator: transport.time_sig_denominator.unwrap_or(4),
+                    loop_range_beats: transport.loop_range_beats(),
+                })
+            }
+            _ => None,
+        };
+        let sender = &self.sender;
+        let cues = &self.cues;
+        let sample_rate = self.input_sample_rate;
+        self.cue_trigger
+            .process(cues, playhead, sample_rate, num_samples, |index, timing| {
+                sender.send(OscChannelMessageType::Cue(OscCueType {
+                    cues: cues.clone(),
+                    index,
+                    offset: timing as f32 / sample_rate,
+                }))
+            })?;
+        Ok(())
+    }
+
+    fn process_resampler(&mut self, context: &mut impl ProcessContext<Self>) {
+        if self
+            .resampler_dirty
+            .compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            nih_trace!(
+                "Resampler Dirty: {} {:?}",
+                self.audio_sample_rate(),
+                self.params.audio_source.value()
+            );
+            //A source without channels has nothing to resample
+            let channels = self.source_channels(self.params.audio_source.value());
+            if channels > 0 {
+                context.execute_background(DawOutTask::BuildResampler {
+                    input_sample_rate: self.input_sample_rate as usize,
+                    output_sample_rate: self.audio_sample_rate(),
+                    channels,
+                });
+            }
+        }
+        while let Ok(resampler) = self.resampler_receiver.try_recv() {
+            //Swap in the rebuilt resampler, the old one is freed by the background thread
+            let channels = self.source_channels(self.params.audio_source.value());
+            let retired = if resampler.input_sample_rate() == self.input_sample_rate as usize
+                && resampler.channels() == channels
+            {
+                self.resampler.replace(resampler)
+            } else {
+                Some(resampler)
+            };
+            if let Some(retired) = retired {
+                context.execute_background(DawOutTask::DropResampler(retired));
+            }
+        }
+    }
+
+    fn process_audio_buffer(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+    ) -> Result<()> {
+        let source: &[&mut [f32]] = match self.params.audio_source.value() {
+            AudioSource::Main => buffer.as_slice(),
+            AudioSource::Sidechain => match aux.inputs.first_mut() {
+                Some(sidechain) => sidechain.as_slice(),
+                None => &mut [],
+            },
+        };
+        if self.params.audio_format.value() == AudioFormat::Waveform {
+            return self.process_waveform(source);
+        }
         if let Some(resampler) = &mut self.resampler {
-            if let Some(resampler_buffer) = &mut self.resampler_buffer {
-                //TODO: deal with a create mono signal or send out multiple channels?
-                resampler.process_into_buffer(&buffer.as_slice(), resampler_buffer, None)?;
-                //TODO: we only use the first channel
-                for &sample in &resampler_buffer[0] {
-                    if sample == 0.0 {
-                        continue;
+            //A rebuild for a newly selected source or format is still pending
+            if resampler.channels() != source.len()
+                || (self.params.audio_format.value() == AudioFormat::Samples
+                    && resampler.output_sample_rate() > MAX_PER_SAMPLE_RATE)
+            {
+                return Ok(());
+            }
+            let sender = &self.sender;
+            let audio_pool = &self.audio_pool;
+            let wait = self.wait_for_buffers();
+            let audio_frames = &mut self.audio_frames;
+            let mode = self.params.audio_channel_mode.value();
+            let format = self.params.audio_format.value();
+            let sample_rate = resampler.output_sample_rate() as u32;
+            let input_sample_rate = self.input_sample_rate;
+            resampler.process(source, |resampler_buffer, end| {
+                let frames = resampler_buffer.first().map_or(0, Vec::len);
+                //The chunk ends where it was completed, earlier frames can be from previous blocks
+                let chunk_offset =
+                    end as f32 / input_sample_rate - frames as f32 / sample_rate as f32;
+                let frame_offset = |frame: usize| chunk_offset + frame as f32 / sample_rate as f32;
+                if format == AudioFormat::Samples {
+                    for frame in 0..frames {
+                        sender.send(OscChannelMessageType::Audio(OscAudioType::from_frame(
+                            mode,
+                            resampler_buffer,
+                            frame,
+                            frame_offset(frame),
+                        )))?;
+                    }
+                } else {
+                    let mut frame = 0;
+                    while frame < frames {
+                        //Realtime drops the rest of the block if the OSC thread still holds every
+                        //buffer, receivers see the gap in the frame counter
+                        let mut values = match audio_pool.take(wait) {
+                            Some(values) => values,
+                            None => break,
+                        };
+                        let first_frame = frame;
+                        let mut channels = 0;
+                        while frame < frames
+                            && values.len() + MAX_AUDIO_CHANNELS <= AUDIO_BLOCK_MAX_SAMPLES
+                        {
+                            let audio =
+                                OscAudioType::from_frame(mode, resampler_buffer, frame, 0.0);
+                            values.extend_from_slice(&audio.values[..audio.channels as usize]);
+                            channels = audio.channels;
+                            frame += 1;
+                        }
+                        sender.send(OscChannelMessageType::AudioBlock(OscAudioBlockType {
+                            values,
+                            frame: audio_frames.wrapping_add(first_frame as u32),
+                            sample_rate,
+                            channels,
+                            blob: format == AudioFormat::BlobBlock,
+                            offset: frame_offset(first_frame),
+                        }))?;
                     }
-                    let send_result = self
-                        .sender
-                        .send(OscChannelMessageType::Audio(OscAudioType { value: sample }));
-                    if send_result.is_err() {
-                        nih_error!("Failed to send processed audio {:?}", send_result.unwrap_err());
-                        break;
+                }
+                *audio_frames = audio_frames.wrapping_add(frames as u32);
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn process_waveform(&mut self, source: &[&mut [f32]]) -> Result<()> {
+        let mode = self.params.audio_channel_mode.value();
+        let channels = match mode {
+            AudioChannelMode::Left => &source[..source.len().min(1)],
+            AudioChannelMode::Right => {
+                //Mono input falls back to its only channel
+                let right = source.len().min(2).saturating_sub(1);
+                &source[right..source.len().min(right + 1)]
+            }
+            AudioChannelMode::MonoSum | AudioChannelMode::AllChannels => source,
+        };
+        let mono = mode == AudioChannelMode::MonoSum;
+        self.waveform_clock
+            .set_rate(self.input_sample_rate, self.params.osc_sample_rate.value());
+        let num_samples = channels.first().map_or(0, |channel| channel.len());
+        let mut start = 0;
+        while start < num_samples {
+            let (range, report) = self.waveform_clock.next_segment(start, num_samples);
+            start = range.end;
+            self.waveform.process(channels, range.clone(), mono);
+            if report {
+                //Realtime skips the bucket if the OSC thread still holds every buffer
+                match self.float_pool.take(self.wait_for_buffers()) {
+                    Some(mut values) => {
+                        self.waveform.take(&mut values);
+                        self.sender
+                            .send(OscChannelMessageType::Waveform(OscFloatsType {
+                                values,
+                                offset: range.end as f32 / self.input_sample_rate,
+                            }))?;
                     }
+                    None => self.waveform.clear(),
                 }
             }
         }
         Ok(())
     }
 
+    fn process_analysis(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        transport: &Transport,
+    ) -> Result<()> {
+        if self
+            .loudness_reset
+            .compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            nih_trace!("Integrated Loudness Reset");
+            self.loudness.reset_integrated();
+            self.sender.send(OscChannelMessageType::LoudnessReset)?;
+        }
+        let send_beat_features = self.params.flag_send_beat_features.value();
+        let send_levels = self.params.flag_send_levels.value()
+            || send_beat_features && self.params.beat_feature_rms.value();
+        let send_loudness = self.params.flag_send_loudness.value();
+        let send_stereo = self.params.flag_send_stereo.value();
+        let analyze_spectrum = self.spectrum_needed();
+        let send_bands = self.params.flag_send_bands.value()
+            || send_beat_features && self.params.beat_feature_bands.value();
+        let send_zcr = self.params.flag_send_zcr.value();
+        let send_onset = self.params.flag_send_onset.value();
+        let send_pitch = self.params.flag_send_pitch.value();
+        let send_bpm = self.params.flag_send_bpm.value();
+        let send_gates = self.params.flag_send_gates.value();
+        let send_notes = self.params.flag_send_audio_notes.value();
+        let send_signal = self.params.flag_send_signal.value();
+        let sample_rate = self.input_sample_rate;
+        if !send_signal {
+            //The state is sent again when the detector is switched back on
+            self.signal.clear();
+        }
+        //Beats are only known while the host is playing
+        let beat_clock = match (transport.pos_beats(), transport.tempo) {
+            (Some(pos_beats), Some(tempo)) if transport.playing && send_beat_features => Some((
+                pos_beats,
+                tempo / 60.0 / sample_rate as f64,
+                transport.loop_range_beats(),
+            )),
+            _ => {
+                self.beat_features.clear();
+                None
+            }
+        };
+        let time_signature = (
+            transport.time_sig_numerator.unwrap_or(4),
+            transport.time_sig_denominator.unwrap_or(4),
+        );
+        if !send_notes {
+            //Don't leave a note hanging when audio notes are switched off
+            let sender = &self.sender;
+            self.notes.release(0, |index, change| {
+                let offset = index as f32 / sample_rate;
+                sender.send(OscNoteType::from_change(change, offset))?;
+                Ok(())
+            })?;
+        }
+        //MIDI only layouts have nothing to analyze
+        if self.input_channels == 0 && self.sidechain_channels == 0 {
+            return Ok(());
+        }
+        if !send_levels
+            && !send_loudness
+            && !send_stereo
+            && !analyze_spectrum
+            && !send_bands
+            && !send_zcr
+            && !send_onset
+            && !send_pitch
+            && !send_bpm
+            && !send_gates
+            && !send_notes
+            && !send_signal
+            && !send_beat_features
+        {
+            return Ok(());
+        }
+        self.report_clock
+            .set_rate(sample_rate, self.params.osc_sample_rate.value());
+        self.level_meter.set_times(
+            self.params.level_attack_ms.value(),
+            self.params.level_release_ms.value(),
+            sample_rate,
+        );
+        self.crossover.set_times(
+            self.params.level_attack_ms.value(),
+            self.params.level_release_ms.value(),
+            sample_rate,
+        );
+        self.crossover.set_bands(
+            self.params.crossover_bands.value() as usize,
+            [
+                self.params.crossover_low_hz.value(),
+                self.params.crossover_mid_hz.value(),
+                self.params.crossover_high_hz.value(),
+            ],
+        );
+        let onset_sensitivity = self.params.onset_sensitivity.value();
+        let pitch_confidence = self.params.pitch_confidence.value();
+
+        let num_samples = buffer.samples();
+        let main: &[&mut [f32]] = buffer.as_slice();
+        let sidechain: &[&mut [f32]] = match aux.inputs.first_mut() {
+            Some(sidechain) => sidechain.as_slice(),
+            None => &mut [],
+        };
+        let source = |source: AudioSource| match source {
+            AudioSource::Main => main,
+            AudioSource::Sidechain => sidechain,
+        };
+        let levels_channels = source(self.params.levels_source.value());
+        let loudness_channels = source(self.params.loudness_source.value());
+        let stereo_channels = source(self.params.stereo_source.value());
+        let spectrum_channels = source(self.params.spectrum_source.value());
+        let bands_channels = source(self.params.bands_source.value());
+        let onset_channels = source(self.params.onset_source.value());
+        let pitch_channels = source(self.params.pitch_source.value());
+        let gate_channels = source(self.params.gate_source.value());
+        let signal_channels = source(self.params.signal_source.value());
+        let signal_threshold = self.params.signal_threshold.value();
+        let signal_timeout_ms = self.params.signal_timeout_ms.value();
+        let gate_settings = GateSettings {
+            count: self.params.gate_count.value() as usize,
+            thresholds: [
+                self.params.gate1_threshold.value(),
+                self.params.gate2_threshold.value(),
+                self.params.gate3_threshold.value(),
+                self.params.gate4_threshold.value(),
+            ],
+            hysteresis_db: self.params.gate_hysteresis.value(),
+            hold_ms: self.params.gate_hold_ms.value(),
+        };
+        let mut start = 0;
+        while start < num_samples {
+            let (range, report) = self.report_clock.next_segment(start, num_samples);
+            start = range.end;
+            if send_levels {
+                self.level_meter.process(levels_channels, range.clone());
+            }
+            if send_loudness {
+                self.loudness.process(loudness_channels, range.clone());
+            }
+            if send_stereo {
+                self.stereo.process(stereo_channels, range.clone());
+            }
+            if analyze_spectrum {
+                self.spectrum.process(spectrum_channels, range.clone());
+            }
+            if send_bands {
+                self.crossover.process(bands_channels, range.clone());
+            }
+            if send_zcr {
+                self.features.process(spectrum_channels, range.clone());
+            }
+            if send_onset || send_bpm || send_notes {
+                let sender = &self.sender;
+                let tempo = &mut self.tempo;
+                let notes = &mut self.notes;
+                self.onset
+                    .process(onset_channels, range.clone(), onset_sensitivity, |frame| {
+                        if let Some(strength) = frame.onset {
+                            if send_onset {
+                                sender.send(OscChannelMessageType::Onset(OscOnsetType {
+                                    strength,
+                                    offset: frame.index as f32 / sample_rate,
+                                }))?;
+                            }
+                            if send_notes {
+                                notes.onset(frame.index, strength);
+                            }
+                        }
+                        if send_bpm {
+                            if let Some((bpm, confidence)) = tempo.process(frame.flux) {
+                                sender.send(OscChannelMessageType::BpmDetected(
+                                    OscBpmDetectedType {
+                                        bpm,
+                                        confidence,
+                                        offset: frame.index as f32 / sample_rate,
+                                    },
+                                ))?;
+                            }
+                        }
+                        Ok(())
+                    })?;
+            }
+            if send_pitch || send_notes {
+                let sender = &self.sender;
+                let notes = &mut self.notes;
+                self.pitch.process(
+                    pitch_channels,
+                    range.clone(),
+                    pitch_confidence,
+                    |index, frequency, confidence| {
+                        if send_pitch {
+                            sender.send(OscChannelMessageType::Pitch(OscPitchType {
+                                frequency,
+                                confidence,
+                                offset: index as f32 / sample_rate,
+                            }))?;
+                        }
+                        if send_notes {
+                            notes.pitch(index, frequency, |index, change| {
+                                let offset = index as f32 / sample_rate;
+                                sender.send(OscNoteType::from_change(change, offset))?;
+                                Ok(())
+                            })?;
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+            if send_notes {
+                let sender = &self.sender;
+                self.notes.advance(range.end, |index, change| {
+                    let offset = index as f32 / sample_rate;
+                    sender.send(OscNoteType::from_change(change, offset))?;
+                    Ok(())
+                })?;
+            }
+            if send_gates {
+                let sender = &self.sender;
+                self.gates.process(
+                    gate_channels,
+                    range.clone(),
+                    &gate_settings,
+                    |index, gate, open| {
+                        sender.send(OscChannelMessageType::Gate(OscGateType {
+                            index: gate as u8 + 1,
+                            open,
+                            offset: index as f32 / sample_rate,
+                        }))?;
+                        Ok(())
+                    },
+                )?;
+            }
+            if send_signal {
+                let sender = &self.sender;
+                self.signal.process(
+                    signal_channels,
+                    range.clone(),
+                    signal_threshold,
+                    signal_timeout_ms,
+                    |index, present| {
+                        sender.send(OscChannelMessageType::Signal(OscSignalType {
+                            present,
+                            offset: index as f32 / sample_rate,
+                        }))?;
+                        Ok(())
+                    },
+                )?;
+            }
+            let offset = range.end as f32 / sample_rate;
+            if report {
+                self.send_analysis(offset)?;
+            }
+            //Every segment counts towards its beat, even when reports are further apart
+            if let Some((pos_beats, beats_per_sample, loop_range_beats)) = beat_clock {
+                if !report && self.params.beat_feature_centroid.value() {
+                    self.spectrum.analyze()?;
+                }
+                let mut pos_beats = pos_beats + (range.end - 1) as f64 * beats_per_sample;
+                //A block that runs past the end of the host loop continues from the loop start
+                if let Some((loop_start, loop_end)) = loop_range_beats {
+                    if pos_beats >= loop_end && loop_end > loop_start {
+                        pos_beats = loop_start + pos_beats - loop_end;
+                    }
+                }
+                self.send_beat_features(pos_beats, time_signature, range.len(), offset)?;
+            }
+        }
+        self.notes.end_block(num_samples);
+        Ok(())
+    }
+
+    /// Sends every enabled analysis result, `offset` is the report point inside the block in
+    /// seconds
+    fn send_analysis(&mut self, offset: f32) -> Result<()> {
+        if self.params.flag_send_levels.value() {
+            let mut rms = OscLevelType::default();
+            let mut peak = OscLevelType::default();
+            let channels = self
+                .level_meter
+                .channels()
+                .min(self.source_channels(self.params.levels_source.value()))
+                .min(MAX_AUDIO_CHANNELS);
+            for channel in 0..channels {
+                rms.values[channel] = self.level_meter.rms(channel);
+                peak.values[channel] = self.level_meter.peak(channel);
+            }
+            rms.channels = channels as u8;
+            peak.channels = channels as u8;
+            rms.offset = offset;
+            peak.offset = offset;
+            self.sender.send(OscChannelMessageType::LevelRms(rms))?;
+            self.sender.send(OscChannelMessageType::LevelPeak(peak))?;
+        }
+        if self.params.flag_send_loudness.value() {
+            let loudness = &mut self.loudness;
+            let measurements = [
+                (LoudnessMeasurement::Momentary, loudness.momentary()),
+                (LoudnessMeasurement::ShortTerm, loudness.short_term()),
+                (LoudnessMeasurement::Integrated, loudness.integrated()),
+                (LoudnessMeasurement::TruePeak, loudness.take_true_peak()),
+                (LoudnessMeasurement::TruePeakMax, loudness.true_peak_max()),
+            ];
+            for (measurement, value) in measurements {
+                self.sender
+                    .send(OscChannelMessageType::Loudness(OscLoudnessType {
+                        measurement,
+                        value,
+                        offset,
+                    }))?;
+            }
+        }
+        if self.params.flag_send_stereo.value() {
+            let measurements = [
+                (StereoMeasurement::Correlation, self.stereo.correlation()),
+                (StereoMeasurement::Balance, self.stereo.balance()),
+                (StereoMeasurement::Width, self.stereo.width()),
+            ];
+            for (measurement, value) in measurements {
+                self.sender
+                    .send(OscChannelMessageType::Stereo(OscStereoType {
+                        measurement,
+                        value,
+                        offset,
+                    }))?;
+            }
+        }
+        if self.spectrum_needed() {
+            self.spectrum.analyze()?;
+        }
+        if self.params.flag_send_spectrum.value() {
+            //Realtime skips the frame if the OSC thread still holds every buffer
+            if let Some(mut values) = self.float_pool.take(self.wait_for_buffers()) {
+                let count = self.params.spectrum_bands.value() as usize;
+                values.resize(count, 0.0);
+                self.spectrum.bands(count, &mut values);
+                self.sender
+                    .send(OscChannelMessageType::Spectrum(OscFloatsType {
+                        values,
+                        offset,
+                    }))?;
+            }
+        }
+        if self.params.flag_send_bands.value() {
+            let mut values = [0.0; MAX_CROSSOVER_BANDS];
+            let bands = self.crossover.bands();
+            for (band, value) in values[..bands].iter_mut().enumerate() {
+                *value = self.crossover.rms(band);
+            }
+            self.sender
+                .send(OscChannelMessageType::Bands(OscBandsType {
+                    values,
+                    bands: bands as u8,
+                    offset,
+                }))?;
+        }
+        if self.send_spectral_features() {
+            let magnitudes = self.spectrum.magnitudes();
+            let bin_width = self.spectrum.bin_width();
+            self.features.update(magnitudes);
+            let descriptors = [
+                (
+                    self.params.flag_send_centroid.value(),
+                    SpectralFeature::Centroid,
+                    features::centroid(magnitudes, bin_width),
+                ),
+                (
+                    self.params.flag_send_flatness.value(),
+                    SpectralFeature::Flatness,
+                    features::flatness(magnitudes),
+                ),
+                (
+                    self.params.flag_send_rolloff.value(),
+                    SpectralFeature::Rolloff,
+                    features::rolloff(magnitudes, bin_width),
+                ),
+                (
+                    self.params.flag_send_flux.value(),
+                    SpectralFeature::Flux,
+                    self.features.flux(),
+                ),
+            ];
+            for (enabled, feature, value) in descriptors {
+                if enabled {
+                    self.sender
+                        .send(OscChannelMessageType::Feature(OscFeatureType {
+                            feature,
+                            value,
+                            offset,
+                        }))?;
+                }
+            }
+        }
+        let send_chroma = self.params.flag_send_chroma.value();
+        let send_key = self.params.flag_send_key.value();
+        if send_chroma || send_key {
+            let elapsed = self.report_clock.interval() as f32 / self.input_sample_rate;
+            self.chroma.update(self.spectrum.magnitudes(), elapsed);
+        }
+        if send_chroma {
+            if let Some(mut values) = self.float_pool.take(self.wait_for_buffers()) {
+                values.extend_from_slice(self.chroma.chroma());
+                self.sender
+                    .send(OscChannelMessageType::Chroma(OscFloatsType {
+                        values,
+                        offset,
+                    }))?;
+            }
+        }
+        if send_key {
+            //The estimate moves slowly, only changes of key are sent
+            let key = self.chroma.key();
+            if key != self.sent_key {
+                if let Some((tonic, minor)) = key {
+                    self.sender.send(OscChannelMessageType::Key(OscKeyType {
+                        tonic,
+                        minor,
+                        offset,
+                    }))?;
+                }
+                self.sent_key = key;
+            }
+        } else {
+            self.sent_key = None;
+        }
+        if self.params.flag_send_zcr.value() {
+            self.sender
+                .send(OscChannelMessageType::Feature(OscFeatureType {
+                    feature: SpectralFeature::ZeroCrossingRate,
+                    value: self.features.take_zero_crossing_rate(),
+                    offset,
+                }))?;
+        }
+        Ok(())
+    }
+
+    /// Adds the features at the end of a segment of `samples` to the running beat, sends the
+    /// averages of the previous beat once a new one begins
+    fn send_beat_features(
+        &mut self,
+        pos_beats: f64,
+        time_signature: (i32, i32),
+        samples: usize,
+        offset: f32,
+    ) -> Result<()> {
+        let (numerator, denominator) = time_signature;
+        let mut position = BeatPosition::from_quarter_notes(pos_beats, numerator, denominator);
+        if self.params.beat_features_period.value() == BeatPeriod::Bar {
+            position.beat = 1;
+        }
+        let mut values = [0.0; MAX_BEAT_FEATURES];
+        let mut count = 0;
+        if self.params.beat_feature_rms.value() {
+            let channels = self
+                .level_meter
+                .channels()
+                .min(self.source_channels(self.params.levels_source.value()));
+            let sum: f32 = (0..channels)
+                .map(|channel| self.level_meter.rms(channel))
+                .sum();
+            values[count] = sum / channels.max(1) as f32;
+            count += 1;
+        }
+        if self.params.beat_feature_bands.value() {
+            for band in 0..self.crossover.bands() {
+                values[count] = self.crossover.rms(band);
+                count += 1;
+            }
+        }
+        if self.params.beat_feature_centroid.value() {
+            values[count] =
+                features::centroid(self.spectrum.magnitudes(), self.spectrum.bin_width());
+            count += 1;
+        }
+        let sender = &self.sender;
+        self.beat_features.add(
+            pos_beats,
+            position,
+            &values[..count],
+            samples,
+            |position, averages| {
+                let mut values = [0.0; MAX_BEAT_FEATURES];
+                values[..averages.len()].copy_from_slice(averages);
+                sender.send(OscChannelMessageType::BeatFeatures(OscBeatFeaturesType {
+                    position,
+                    values,
+                    count: averages.len() as u8,
+                    offset,
+                }))
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Offline renders wait for pooled buffers instead of dropping messages, so the capture is
+    /// complete like the messages that wait on the channel
+    fn wait_for_buffers(&self) -> bool {
+        self.process_mode == ProcessMode::Offline
+    }
+
+    /// Rate audio is resampled to, per sample messages are capped at `MAX_PER_SAMPLE_RATE`
+    fn audio_sample_rate(&self) -> usize {
+        let sample_rate = self.params.osc_sample_rate.value() as usize;
+        match self.params.audio_format.value() {
+            AudioFormat::Samples => sample_rate.min(MAX_PER_SAMPLE_RATE),
+            _ => sample_rate,
+        }
+    }
+
+    fn source_channels(&self, source: AudioSource) -> usize {
+        match source {
+            AudioSource::Main => self.input_channels,
+            AudioSource::Sidechain => self.sidechain_channels,
+        }
+    }
+
+    /// Everything that reads the spectrum analyzer FFT
+    fn spectrum_needed(&self) -> bool {
+        self.params.flag_send_spectrum.value()
+            || self.send_spectral_features()
+            || self.params.flag_send_chroma.value()
+            || self.params.flag_send_key.value()
+            || self.params.flag_send_beat_features.value()
+                && self.params.beat_feature_centroid.value()
+    }
+
+    fn send_spectral_features(&self) -> bool {
+        self.params.flag_send_centroid.value()
+            || self.params.flag_send_flatness.value()
+            || self.params.flag_send_rolloff.value()
+            || self.params.flag_send_flux.value()
+    }
+
     fn kill_background_thread(&mut self) {
-        let exit_result = self.sender.send(OscChannelMessageType::Exit);
-        if exit_result.is_err() {
-            nih_error!(
-                "Failed to send shutdown to background thread {:?}",
-                exit_result.unwrap_err()
-            );
+        if let Some(osc_thread) = self.osc_thread.take() {
+            let exit_result = self.sender.send(OscChannelMessageType::Exit);
+            if exit_result.is_err() {
+                nih_error!(
+                    "Failed to send shutdown to background thread {:?}",
+                    exit_result.unwrap_err()
+                );
+                return;
+            }
+            //Take back the receiver so the next initialize can respawn the thread
+            match osc_thread.join() {
+                Ok(receiver) => self.receiver = Some(receiver),
+                Err(e) => nih_error!("Background thread panicked {:?}", e),
+            }
         }
-        self.osc_thread = None;
     }
 }
 
 // /<osc_address_base>/param/<param_name>
 // /<osc_address_base>/note_on <channel> <note> <velocity>
 // /<osc_address_base>/note_off <channel> <note> <velocity>
-// /<osc_address_base>/audio
+// /<osc_address_base>/audio <sample_values>
+// /<osc_address_base>/audio_block <frame> <sample_rate> <channels> <interleaved_samples | blob>
+// /<osc_address_base>/waveform <min> <max> [<min> <max> ...]
+// /<osc_address_base>/level/rms <channel_values>
+// /<osc_address_base>/level/peak <channel_values>
+// /<osc_address_base>/loudness/momentary <lufs>
+// /<osc_address_base>/loudness/short_term <lufs>
+// /<osc_address_base>/loudness/integrated <lufs>
+// /<osc_address_base>/loudness/true_peak <dbtp>
+// /<osc_address_base>/loudness/true_peak_max <dbtp>
+// /<osc_address_base>/loudness/reset
+// /<osc_address_base>/stereo/correlation <correlation>
+// /<osc_address_base>/stereo/balance <balance>
+// /<osc_address_base>/stereo/width <width>
+// /<osc_address_base>/spectrum <band_values>
+// /<osc_address_base>/bands <low> <mid> <high> | <low> <low_mid> <high_mid> <high>
+// /<osc_address_base>/feature/centroid <hz>
+// /<osc_address_base>/feature/flatness <flatness>
+// /<osc_address_base>/feature/rolloff <hz>
+// /<osc_address_base>/feature/flux <flux>
+// /<osc_address_base>/feature/zcr <rate>
+// /<osc_address_base>/chroma <c> <c#> <d> <d#> <e> <f> <f#> <g> <g#> <a> <a#> <b>
+// /<osc_address_base>/key <tonic> <mode>
+// /<osc_address_base>/onset <strength>
+// /<osc_address_base>/pitch <hz> <midi_note> <cents> <confidence>
+// /<osc_address_base>/bpm_detected <bpm> <confidence>
+// /<osc_address_base>/gate <index> <1 | 0>
+// /<osc_address_base>/signal <1 | 0>
+// /<osc_address_base>/beat_features <bar> <beat> [<rms>] [<band_values>] [<centroid>]
+// /<osc_address_base>/clock
+// /<osc_address_base>/start
+// /<osc_address_base>/stop
+// /<osc_address_base>/continue
+// /<osc_address_base>/timecode <hours> <minutes> <seconds> <frames>
+// /<cue_address> <cue_args>
 
 fn osc_client_worker(
     socket: UdpSocket,
     param_address_base: String,
+    float_pool: FloatPool,
+    audio_pool: FloatPool,
     recv: Receiver<OscChannelMessageType>,
-) -> () {
+) -> Receiver<OscChannelMessageType> {
     nih_trace!("Background thread spawned!");
     nih_trace!("Background thread OSC Address Base: {}", param_address_base);
     let mut address_base = format_osc_address_base(&param_address_base);
     let mut connected = true; //We assume the socket we get is good
+    let mut timetag_latency = None; //Plain messages until timetags are enabled
+    let mut capturing = false; //Messages never go to the socket while capturing
+    let mut capture: Option<BufWriter<File>> = None;
+    let mut song_position = 0.0;
+    let mut block_time = SystemTime::now();
     while let Some(channel_message) = recv.recv().ok() {
+        let offset = channel_message.offset();
         let osc_message = match channel_message {
             OscChannelMessageType::Exit => break,
             OscChannelMessageType::ConnectionChange(message) => {
@@ -543,6 +2463,33 @@ fn osc_client_worker(
                 nih_trace!("AddressBase Change: {}", address_base);
                 continue;
             }
+            OscChannelMessageType::TimetagChange(message) => {
+                timetag_latency = message.latency;
+                nih_trace!("Timetag Change: {:?}", timetag_latency);
+                continue;
+            }
+            OscChannelMessageType::CaptureChange(message) => {
+                nih_trace!("Capture Change: {:?}", message.path);
+                close_capture(capture.take());
+                song_position = 0.0;
+                capturing = message.path.is_some();
+                if let Some(path) = message.path {
+                    match File::create(&path) {
+                        Ok(file) => capture = Some(BufWriter::new(file)),
+                        Err(e) => nih_error!(
+                            "Failed to create capture file {}, messages will be dropped {:?}",
+                            path,
+                            e
+                        ),
+                    }
+                }
+                continue;
+            }
+            OscChannelMessageType::SongPosition(message) => {
+                song_position = message.seconds;
+                block_time = message.time;
+                continue;
+            }
             OscChannelMessageType::Param(message) => OscMessage {
                 addr: format!("{}/param/{}", address_base, message.name),
                 args: vec![OscType::Float(message.value)],
@@ -565,11 +2512,209 @@ fn osc_client_worker(
             },
             OscChannelMessageType::Audio(message) => OscMessage {
                 addr: format!("{}/audio", address_base),
-                args: vec![OscType::Float(message.value)],
+                args: osc_float_args(&message.values[..message.channels as usize]),
+            },
+            OscChannelMessageType::AudioBlock(message) => {
+                let mut args = vec![
+                    OscType::Int(message.frame as i32),
+                    OscType::Int(message.sample_rate as i32),
+                    OscType::Int(message.channels as i32),
+                ];
+                if message.blob {
+                    let bytes = message.values.iter().flat_map(|value| value.to_le_bytes());
+                    args.push(OscType::Blob(bytes.collect()));
+                } else {
+                    args.extend(osc_float_args(&message.values));
+                }
+                audio_pool.give(message.values);
+                OscMessage {
+                    addr: format!("{}/audio_block", address_base),
+                    args,
+                }
+            }
+            OscChannelMessageType::LevelRms(message) => OscMessage {
+                addr: format!("{}/level/rms", address_base),
+                args: osc_float_args(&message.values[..message.channels as usize]),
+            },
+            OscChannelMessageType::LevelPeak(message) => OscMessage {
+                addr: format!("{}/level/peak", address_base),
+                args: osc_float_args(&message.values[..message.channels as usize]),
+            },
+            OscChannelMessageType::Loudness(message) => {
+                let name = match message.measurement {
+                    LoudnessMeasurement::Momentary => "momentary",
+                    LoudnessMeasurement::ShortTerm => "short_term",
+                    LoudnessMeasurement::Integrated => "integrated",
+                    LoudnessMeasurement::TruePeak => "true_peak",
+                    LoudnessMeasurement::TruePeakMax => "true_peak_max",
+                };
+                OscMessage {
+                    addr: format!("{}/loudness/{}", address_base, name),
+                    args: vec![OscType::Float(message.value)],
+                }
+            }
+            OscChannelMessageType::LoudnessReset => OscMessage {
+                addr: format!("{}/loudness/reset", address_base),
+                args: vec![],
+            },
+            OscChannelMessageType::Stereo(message) => {
+                let name = match message.measurement {
+                    StereoMeasurement::Correlation => "correlation",
+                    StereoMeasurement::Balance => "balance",
+                    StereoMeasurement::Width => "width",
+                };
+                OscMessage {
+                    addr: format!("{}/stereo/{}", address_base, name),
+                    args: vec![OscType::Float(message.value)],
+                }
+            }
+            OscChannelMessageType::Spectrum(message) => {
+                let args = osc_float_args(&message.values);
+                float_pool.give(message.values);
+                OscMessage {
+                    addr: format!("{}/spectrum", address_base),
+                    args,
+                }
+            }
+            OscChannelMessageType::Waveform(message) => {
+                let args = osc_float_args(&message.values);
+                float_pool.give(message.values);
+                OscMessage {
+                    addr: format!("{}/waveform", address_base),
+                    args,
+                }
+            }
+            OscChannelMessageType::Bands(message) => OscMessage {
+                addr: format!("{}/bands", address_base),
+                args: osc_float_args(&message.values[..message.bands as usize]),
+            },
+            OscChannelMessageType::Feature(message) => {
+                let name = match message.feature {
+                    SpectralFeature::Centroid => "centroid",
+                    SpectralFeature::Flatness => "flatness",
+                    SpectralFeature::Rolloff => "rolloff",
+                    SpectralFeature::Flux => "flux",
+                    SpectralFeature::ZeroCrossingRate => "zcr",
+                };
+                OscMessage {
+                    addr: format!("{}/feature/{}", address_base, name),
+                    args: vec![OscType::Float(message.value)],
+                }
+            }
+            OscChannelMessageType::Chroma(message) => {
+                let args = osc_float_args(&message.values);
+                float_pool.give(message.values);
+                OscMessage {
+                    addr: format!("{}/chroma", address_base),
+                    args,
+                }
+            }
+            OscChannelMessageType::Key(message) => OscMessage {
+                addr: format!("{}/key", address_base),
+                args: vec![
+                    OscType::String(PITCH_CLASS_NAMES[message.tonic as usize].to_string()),
+                    OscType::String(if message.minor { "minor" } else { "major" }.to_string()),
+                ],
+            },
+            OscChannelMessageType::Onset(message) => OscMessage {
+                addr: format!("{}/onset", address_base),
+                args: vec![OscType::Float(message.strength)],
+            },
+            OscChannelMessageType::Pitch(message) => {
+                let note = 69.0 + 12.0 * (message.frequency / 440.0).log2();
+                let midi_note = note.round();
+                OscMessage {
+                    addr: format!("{}/pitch", address_base),
+                    args: vec![
+                        OscType::Float(message.frequency),
+                        OscType::Int(midi_note as i32),
+                        OscType::Float((note - midi_note) * 100.0),
+                        OscType::Float(message.confidence),
+                    ],
+                }
+            }
+            OscChannelMessageType::BpmDetected(message) => OscMessage {
+                addr: format!("{}/bpm_detected", address_base),
+                args: vec![
+                    OscType::Float(message.bpm),
+                    OscType::Float(message.confidence),
+                ],
+            },
+            OscChannelMessageType::Gate(message) => OscMessage {
+                addr: format!("{}/gate", address_base),
+                args: vec![
+                    OscType::Int(message.index as i32),
+                    OscType::Int(message.open as i32),
+                ],
+            },
+            OscChannelMessageType::BeatFeatures(message) => {
+                let mut args = vec![
+                    OscType::Int(message.position.bar),
+                    OscType::Int(message.position.beat),
+                ];
+                args.extend(osc_float_args(&message.values[..message.count as usize]));
+                OscMessage {
+                    addr: format!("{}/beat_features", address_base),
+                    args,
+                }
+            }
+            OscChannelMessageType::Signal(message) => OscMessage {
+                addr: format!("{}/signal", address_base),
+                args: vec![OscType::Int(message.present as i32)],
+            },
+            OscChannelMessageType::Clock(_) => OscMessage {
+                addr: format!("{}/clock", address_base),
+                args: vec![],
+            },
+            OscChannelMessageType::Start => OscMessage {
+                addr: format!("{}/start", address_base),
+                args: vec![],
+            },
+            OscChannelMessageType::Stop => OscMessage {
+                addr: format!("{}/stop", address_base),
+                args: vec![],
+            },
+            OscChannelMessageType::Continue => OscMessage {
+                addr: format!("{}/continue", address_base),
+                args: vec![],
+            },
+            OscChannelMessageType::Timecode(message) => OscMessage {
+                addr: format!("{}/timecode", address_base),
+                args: vec![
+                    OscType::Int(message.timecode.hours as i32),
+                    OscType::Int(message.timecode.minutes as i32),
+                    OscType::Int(message.timecode.seconds as i32),
+                    OscType::Int(message.timecode.frames as i32),
+                ],
+            },
+            OscChannelMessageType::Cue(message) => match message.cues.get(message.index) {
+                Some(cue) => cue.osc_message(),
+                None => continue,
             },
         };
-        if connected {
-            let packet = OscPacket::Message(osc_message);
+        if capturing {
+            //Without a capture file the render is dropped instead of flooding the socket
+            let writer = match &mut capture {
+                Some(writer) => writer,
+                None => continue,
+            };
+            //Captured messages are timetagged with their song position
+            let position = Duration::from_secs_f64((song_position + offset as f64).max(0.0));
+            let packet = OscPacket::Bundle(OscBundle {
+                timetag: osc_time_from_duration(position),
+                content: vec![OscPacket::Message(osc_message)],
+            });
+            if let Err(e) = write_capture_packet(writer, &packet) {
+                nih_error!("Failed to write osc message to capture {:?}", e);
+            }
+        } else if connected {
+            let packet = match timetag_latency {
+                Some(latency) => OscPacket::Bundle(OscBundle {
+                    timetag: osc_time_after(block_time, latency + offset),
+                    content: vec![OscPacket::Message(osc_message)],
+                }),
+                None => OscPacket::Message(osc_message),
+            };
             let buf = match rosc::encoder::encode(&packet) {
                 Ok(buf) => buf,
                 Err(e) => {
@@ -590,6 +2735,49 @@ fn osc_client_worker(
             nih_trace!("Sent {:?} packet", packet);
         }
     }
+    close_capture(capture);
+    recv
+}
+
+/// Writes a packet using the OSC 1.0 stream framing, a big endian int32 size followed by the packet
+fn write_capture_packet(writer: &mut BufWriter<File>, packet: &OscPacket) -> Result<()> {
+    let buf = rosc::encoder::encode(packet).map_err(|e| anyhow!("{:?}", e))?;
+    writer.write_all(&(buf.len() as i32).to_be_bytes())?;
+    writer.write_all(&buf)?;
+    Ok(())
+}
+
+fn close_capture(capture: Option<BufWriter<File>>) {
+    if let Some(mut writer) = capture {
+        if let Err(e) = writer.flush() {
+            nih_error!("Failed to flush capture file {:?}", e);
+        }
+    }
+}
+
+/// Seconds between the unix epoch and the NTP epoch used by OSC timetags
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+fn osc_time_after(time: SystemTime, delay: f32) -> OscTime {
+    //Audio resampled from earlier blocks can be timed before its block
+    let time = if delay >= 0.0 {
+        time + Duration::from_secs_f32(delay)
+    } else {
+        time - Duration::from_secs_f32(-delay)
+    };
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    osc_time_from_duration(since_epoch + Duration::from_secs(NTP_UNIX_OFFSET))
+}
+
+fn osc_time_from_duration(duration: Duration) -> OscTime {
+    OscTime {
+        seconds: duration.as_secs() as u32,
+        fractional: (((duration.subsec_nanos() as u64) << 32) / 1_000_000_000) as u32,
+    }
+}
+
+fn osc_float_args(values: &[f32]) -> Vec<OscType> {
+    values.iter().map(|&value| OscType::Float(value)).collect()
 }
 
 fn format_osc_address_base(raw_base: &str) -> String {
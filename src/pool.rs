@@ -0,0 +1,38 @@
+use crossbeam_channel::{Receiver, Sender};
+
+/// Preallocated buffers for messages with a variable number of float arguments. The audio
+/// thread takes a buffer and the OSC thread gives it back once the message is encoded, so
+/// nothing is allocated on the audio thread.
+#[derive(Clone)]
+pub struct FloatPool {
+    sender: Sender<Vec<f32>>,
+    receiver: Receiver<Vec<f32>>,
+}
+
+impl FloatPool {
+    pub fn new(buffers: usize, capacity: usize) -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded(buffers);
+        for _ in 0..buffers {
+            let _ = sender.try_send(Vec::with_capacity(capacity));
+        }
+        Self { sender, receiver }
+    }
+
+    /// Takes an empty buffer. When every buffer is in flight this waits for the OSC thread to
+    /// give one back if `wait` is set and returns `None` otherwise.
+    pub fn take(&self, wait: bool) -> Option<Vec<f32>> {
+        let buffer = if wait {
+            self.receiver.recv().ok()
+        } else {
+            self.receiver.try_recv().ok()
+        };
+        buffer.map(|mut buffer| {
+            buffer.clear();
+            buffer
+        })
+    }
+
+    pub fn give(&self, buffer: Vec<f32>) {
+        let _ = self.sender.try_send(buffer);
+    }
+}
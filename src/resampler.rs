@@ -0,0 +1,85 @@
+use anyhow::{ensure, Result};
+use rubato::{FftFixedOut, Resampler};
+
+/// Downsamples host audio to the OSC sample rate. Host blocks are collected until the resampler
+/// has a full chunk, so any host buffer size works.
+pub struct AudioResampler {
+    resampler: FftFixedOut<f32>,
+    input_buffer: Vec<Vec<f32>>,
+    output_buffer: Vec<Vec<f32>>,
+    input_sample_rate: usize,
+    output_sample_rate: usize,
+}
+
+impl AudioResampler {
+    pub fn new(
+        input_sample_rate: usize,
+        output_sample_rate: usize,
+        channels: usize,
+    ) -> Result<Self> {
+        ensure!(channels > 0, "Resampler needs at least one channel");
+        //Roughly 100ms of output per chunk
+        let chunk_size = (output_sample_rate / 10).max(1);
+        let resampler = FftFixedOut::<f32>::new(
+            input_sample_rate,
+            output_sample_rate,
+            chunk_size,
+            1,
+            channels,
+        )?;
+        let input_buffer = (0..channels)
+            .map(|_| Vec::with_capacity(resampler.input_frames_max()))
+            .collect();
+        let output_buffer = resampler.output_buffer_allocate(true);
+        Ok(Self {
+            resampler,
+            input_buffer,
+            output_buffer,
+            input_sample_rate,
+            output_sample_rate,
+        })
+    }
+
+    pub fn input_sample_rate(&self) -> usize {
+        self.input_sample_rate
+    }
+
+    pub fn output_sample_rate(&self) -> usize {
+        self.output_sample_rate
+    }
+
+    pub fn channels(&self) -> usize {
+        self.input_buffer.len()
+    }
+
+    /// Feeds a block of host audio, `emit` is called with every resampled chunk and the sample
+    /// in the block the chunk was completed at
+    pub fn process<C: AsRef<[f32]>>(
+        &mut self,
+        channels: &[C],
+        mut emit: impl FnMut(&[Vec<f32>], usize) -> Result<()>,
+    ) -> Result<()> {
+        let num_samples = channels.first().map_or(0, |channel| channel.as_ref().len());
+        let mut start = 0;
+        while start < num_samples {
+            let frames_next = self.resampler.input_frames_next();
+            let end = (start + frames_next - self.input_buffer[0].len()).min(num_samples);
+            for (input, channel) in self.input_buffer.iter_mut().zip(channels) {
+                input.extend_from_slice(&channel.as_ref()[start..end]);
+            }
+            start = end;
+            if self.input_buffer[0].len() == frames_next {
+                self.resampler.process_into_buffer(
+                    &self.input_buffer,
+                    &mut self.output_buffer,
+                    None,
+                )?;
+                for input in &mut self.input_buffer {
+                    input.clear();
+                }
+                emit(&self.output_buffer, end)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
 
-use crate::{editor::DawOutEditorEvent, editor::OscSettings, DawOutParams};
+use crate::{cues::parse_cues, editor::DawOutEditorEvent, editor::OscSettings, DawOutParams};
 
 pub struct ParamView;
 
@@ -122,6 +122,15 @@ impl SettingsView {
                     .width(Pixels(180.0));
             })
             .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Capture File").class("label");
+                Textbox::new(cx, settings.map(|settings| settings.capture_path.clone()))
+                    .on_edit(move |cx, text| {
+                        cx.emit(DawOutEditorEvent::SetCapturePath(text));
+                    })
+                    .width(Pixels(180.0));
+            })
+            .class("row");
             HStack::new(cx, |cx| {
                 Label::new(cx, "Send MIDI").class("label");
                 ParamSlider::new(cx, params, |params| &params.flag_send_midi)
@@ -134,6 +143,29 @@ impl SettingsView {
                 .class("widget");
             })
             .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Audio Channels").class("label");
+                ParamSlider::new(cx, params, |params| &params.audio_channel_mode)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Audio Format").class("label");
+                ParamSlider::new(cx, params, |params| &params.audio_format)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Audio Source").class("label");
+                ParamSlider::new(cx, params, |params| &params.audio_source).class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "OSC Sample Rate").class("label");
+                ParamSlider::new(cx, params, |params| &params.osc_sample_rate)
+                    .class("widget");
+            })
+            .class("row");
             VirtualList::new(cx, log, 20.0, |cx, _index, item| {
                 return Label::new(cx, item).left(Pixels(0.0)).class("label");
             })
@@ -148,3 +180,368 @@ impl View for SettingsView {
         Some("generic-ui")
     }
 }
+
+pub struct TransportView;
+
+impl TransportView {
+    pub fn new<S, P>(cx: &mut Context, settings: S, params: P) -> Handle<Self>
+    where
+        S: Lens<Target = OscSettings> + Copy,
+        P: Lens<Target = Arc<DawOutParams>> + Copy,
+    {
+        Self.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send Clock").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_clock)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send Timecode").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_timecode)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Timecode Rate").class("label");
+                ParamSlider::new(cx, params, |params| &params.timecode_rate)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Send Timetags").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_send_timetags)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Latency From Buffer").class("label");
+                ParamSlider::new(cx, params, |params| &params.flag_latency_from_buffer)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Latency Offset").class("label");
+                ParamSlider::new(cx, params, |params| &params.osc_latency_ms)
+                    .class("widget");
+            })
+            .class("row");
+            HStack::new(cx, |cx| {
+                Label::new(cx, "Cues").class("label");
+                Textbox::new(cx, settings.map(|settings| settings.cues.clone()))
+                    .on_edit(move |cx, text| {
+                        cx.toggle_class("invalid", parse_cues(&text).is_err());
+                    })
+                    .on_submit(|cx, text, _| {
+                        cx.emit(DawOutEditorEvent::SetCues(text));
+                    })
+                    .width(Pixels(180.0));
+            })
+            .class("row");
+        })
+    }
+}
+
+impl View for TransportView {
+    fn element(&self) -> Option<&'static str> {
+        Some("generic-ui")
+    }
+}
+
+pub struct AnalysisView;
+
+impl AnalysisView {
+    pub fn new<P>(cx: &mut Context, params: P) -> Handle<Self>
+    where
+        P: Lens<Target = Arc<DawOutParams>> + Copy,
+    {
+        Self.build(cx, |cx| {
+            ScrollView::new(cx, 0.0, 0.0, false, true, |cx| {
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Levels").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_levels)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Level Attack").class("label");
+                    ParamSlider::new(cx, params, |params| &params.level_attack_ms)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Level Release").class("label");
+                    ParamSlider::new(cx, params, |params| &params.level_release_ms)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Levels Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.levels_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Loudness").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_loudness)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Integrated Loudness").class("label");
+                    Button::new(
+                        cx,
+                        |cx| cx.emit(DawOutEditorEvent::ResetLoudness),
+                        |cx| Label::new(cx, "Reset"),
+                    )
+                    .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Loudness Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.loudness_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Stereo").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_stereo).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Stereo Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.stereo_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Spectrum").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_spectrum)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Spectrum Bands").class("label");
+                    ParamSlider::new(cx, params, |params| &params.spectrum_bands)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Spectrum Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.spectrum_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Bands").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_bands).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Crossover Bands").class("label");
+                    ParamSlider::new(cx, params, |params| &params.crossover_bands).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Crossover Low").class("label");
+                    ParamSlider::new(cx, params, |params| &params.crossover_low_hz).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Crossover Mid").class("label");
+                    ParamSlider::new(cx, params, |params| &params.crossover_mid_hz).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Crossover High").class("label");
+                    ParamSlider::new(cx, params, |params| &params.crossover_high_hz)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Bands Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.bands_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Centroid").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_centroid)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Flatness").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_flatness)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Rolloff").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_rolloff)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Flux").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_flux).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Zero Crossings").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_zcr).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Chroma").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_chroma).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Key").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_key).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Onsets").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_onset)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Onset Sensitivity").class("label");
+                    ParamSlider::new(cx, params, |params| &params.onset_sensitivity)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Onset Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.onset_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Pitch").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_pitch).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Pitch Confidence").class("label");
+                    ParamSlider::new(cx, params, |params| &params.pitch_confidence).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Pitch Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.pitch_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Detected BPM").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_bpm).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Audio Notes").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_audio_notes)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Gates").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_gates).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate Count").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate_count).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate 1 Threshold").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate1_threshold).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate 2 Threshold").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate2_threshold).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate 3 Threshold").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate3_threshold).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate 4 Threshold").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate4_threshold).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate Hysteresis").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate_hysteresis).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate Hold").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate_hold_ms).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Gate Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.gate_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Signal").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_signal).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Signal Threshold").class("label");
+                    ParamSlider::new(cx, params, |params| &params.signal_threshold).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Signal Timeout").class("label");
+                    ParamSlider::new(cx, params, |params| &params.signal_timeout_ms)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Signal Source").class("label");
+                    ParamSlider::new(cx, params, |params| &params.signal_source).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Send Beat Features").class("label");
+                    ParamSlider::new(cx, params, |params| &params.flag_send_beat_features)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Beat Features Period").class("label");
+                    ParamSlider::new(cx, params, |params| &params.beat_features_period)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Beat Feature RMS").class("label");
+                    ParamSlider::new(cx, params, |params| &params.beat_feature_rms).class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Beat Feature Bands").class("label");
+                    ParamSlider::new(cx, params, |params| &params.beat_feature_bands)
+                        .class("widget");
+                })
+                .class("row");
+                HStack::new(cx, |cx| {
+                    Label::new(cx, "Beat Feature Centroid").class("label");
+                    ParamSlider::new(cx, params, |params| &params.beat_feature_centroid)
+                        .class("widget");
+                })
+                .class("row");
+            });
+        })
+    }
+}
+
+impl View for AnalysisView {
+    fn element(&self) -> Option<&'static str> {
+        Some("generic-ui")
+    }
+}
@@ -0,0 +1,145 @@
+use crate::grid::{block_timing, BlockWalker};
+use nih_plug::prelude::Enum;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq)]
+pub enum TimecodeRate {
+    #[name = "24 fps"]
+    Fps24,
+    #[name = "25 fps"]
+    Fps25,
+    #[name = "29.97 fps DF"]
+    Fps2997Drop,
+    #[name = "30 fps"]
+    Fps30,
+}
+
+impl TimecodeRate {
+    /// Frames per second of wall clock time
+    fn frame_rate(self) -> f64 {
+        match self {
+            TimecodeRate::Fps24 => 24.0,
+            TimecodeRate::Fps25 => 25.0,
+            TimecodeRate::Fps2997Drop => 30_000.0 / 1_001.0,
+            TimecodeRate::Fps30 => 30.0,
+        }
+    }
+
+    /// Frames per timecode second, drop frame still counts 0-29
+    fn frames_per_second(self) -> u64 {
+        match self {
+            TimecodeRate::Fps24 => 24,
+            TimecodeRate::Fps25 => 25,
+            TimecodeRate::Fps2997Drop | TimecodeRate::Fps30 => 30,
+        }
+    }
+}
+
+pub struct Timecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+}
+
+impl Timecode {
+    fn from_frame(frame: u64, rate: TimecodeRate) -> Self {
+        let mut frame = frame;
+        if rate == TimecodeRate::Fps2997Drop {
+            //Frame numbers 0 and 1 are skipped every minute except every tenth minute
+            const FRAMES_PER_10_MINUTES: u64 = 17_982;
+            const FRAMES_PER_MINUTE: u64 = 1_798;
+            let tens = frame / FRAMES_PER_10_MINUTES;
+            let remainder = frame % FRAMES_PER_10_MINUTES;
+            frame += 18 * tens;
+            if remainder > 1 {
+                frame += 2 * ((remainder - 2) / FRAMES_PER_MINUTE);
+            }
+        }
+        let fps = rate.frames_per_second();
+        Self {
+            hours: ((frame / (fps * 3600)) % 24) as u8,
+            minutes: ((frame / (fps * 60)) % 60) as u8,
+            seconds: ((frame / fps) % 60) as u8,
+            frames: (frame % fps) as u8,
+        }
+    }
+}
+
+/// Generates SMPTE timecode frames from the host playhead
+#[derive(Default)]
+pub struct TimecodeGenerator {
+    frames: BlockWalker,
+}
+
+impl TimecodeGenerator {
+    /// Emits every frame that starts inside the current block along with its sample offset
+    pub fn process<E>(
+        &mut self,
+        playing: bool,
+        pos_seconds: Option<f64>,
+        rate: TimecodeRate,
+        sample_rate: f32,
+        num_samples: usize,
+        mut emit: impl FnMut(Timecode, u32) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let pos_seconds = match pos_seconds {
+            Some(pos_seconds) if playing => pos_seconds,
+            _ => {
+                self.frames.reset();
+                return Ok(());
+            }
+        };
+        let frame_rate = rate.frame_rate();
+        let block_start = pos_seconds * frame_rate;
+        let block_end = (pos_seconds + num_samples as f64 / sample_rate as f64) * frame_rate;
+
+        self.frames.points(block_start, block_end, |frame| {
+            //Pre-roll before zero has no timecode
+            if frame < 0 {
+                return Ok(());
+            }
+            let offset = (frame as f64 / frame_rate - pos_seconds) * sample_rate as f64;
+            emit(
+                Timecode::from_frame(frame as u64, rate),
+                block_timing(offset, num_samples),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timecode(frame: u64, rate: TimecodeRate) -> (u8, u8, u8, u8) {
+        let timecode = Timecode::from_frame(frame, rate);
+        (
+            timecode.hours,
+            timecode.minutes,
+            timecode.seconds,
+            timecode.frames,
+        )
+    }
+
+    #[test]
+    fn drop_frame_skips_first_two_frames_of_the_minute() {
+        let rate = TimecodeRate::Fps2997Drop;
+        assert_eq!(timecode(1_799, rate), (0, 0, 59, 29));
+        assert_eq!(timecode(1_800, rate), (0, 1, 0, 2));
+        assert_eq!(timecode(1_801, rate), (0, 1, 0, 3));
+    }
+
+    #[test]
+    fn drop_frame_keeps_every_tenth_minute() {
+        let rate = TimecodeRate::Fps2997Drop;
+        assert_eq!(timecode(17_981, rate), (0, 9, 59, 29));
+        assert_eq!(timecode(17_982, rate), (0, 10, 0, 0));
+        assert_eq!(timecode(17_983, rate), (0, 10, 0, 1));
+    }
+
+    #[test]
+    fn non_drop_counts_every_frame() {
+        assert_eq!(timecode(1_800, TimecodeRate::Fps30), (0, 1, 0, 0));
+        assert_eq!(timecode(90_000, TimecodeRate::Fps25), (1, 0, 0, 0));
+    }
+}